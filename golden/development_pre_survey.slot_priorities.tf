@@ -0,0 +1,47 @@
+resource "terraform_data" "survey_slot_priority" {
+  triggers_replace = [{ priority = 1, slot_id = aws_lexv2models_slot.survey_age.slot_id }, { priority = 2, slot_id = aws_lexv2models_slot.survey_gender.slot_id }]
+
+  provisioner "local-exec" {
+    command = "aws lexv2-models update-intent --cli-input-json \"$(aws lexv2-models describe-intent --bot-id \"$bot_id\" --bot-version \"$bot_version\" --locale-id \"$locale_id\" --intent-id \"$intent_id\" | jq --argjson slots \"$slot_priority\" 'del(.creationDateTime, .lastUpdatedDateTime) | .slotPriorities = [$slots[] | { priority, slotId: .slot_id }]')\""
+
+    environment {
+      bot_id        = aws_lexv2models_bot.development_pre_survey.id
+      bot_version   = aws_lexv2models_bot_locale.development_pre_survey.bot_version
+      locale_id     = aws_lexv2models_bot_locale.development_pre_survey.locale_id
+      intent_id     = aws_lexv2models_intent.survey.intent_id
+      slot_priority = jsonencode(self.triggers_replace)
+    }
+  }
+}
+
+resource "terraform_data" "gender_why_slot_priority" {
+  triggers_replace = [{ priority = 1, slot_id = aws_lexv2models_slot.gender_why_gender.slot_id }]
+
+  provisioner "local-exec" {
+    command = "aws lexv2-models update-intent --cli-input-json \"$(aws lexv2-models describe-intent --bot-id \"$bot_id\" --bot-version \"$bot_version\" --locale-id \"$locale_id\" --intent-id \"$intent_id\" | jq --argjson slots \"$slot_priority\" 'del(.creationDateTime, .lastUpdatedDateTime) | .slotPriorities = [$slots[] | { priority, slotId: .slot_id }]')\""
+
+    environment {
+      bot_id        = aws_lexv2models_bot.development_pre_survey.id
+      bot_version   = aws_lexv2models_bot_locale.development_pre_survey.bot_version
+      locale_id     = aws_lexv2models_bot_locale.development_pre_survey.locale_id
+      intent_id     = aws_lexv2models_intent.gender_why.intent_id
+      slot_priority = jsonencode(self.triggers_replace)
+    }
+  }
+}
+
+resource "terraform_data" "survey_start_slot_priority" {
+  triggers_replace = [{ priority = 1, slot_id = aws_lexv2models_slot.survey_start_about_self.slot_id }]
+
+  provisioner "local-exec" {
+    command = "aws lexv2-models update-intent --cli-input-json \"$(aws lexv2-models describe-intent --bot-id \"$bot_id\" --bot-version \"$bot_version\" --locale-id \"$locale_id\" --intent-id \"$intent_id\" | jq --argjson slots \"$slot_priority\" 'del(.creationDateTime, .lastUpdatedDateTime) | .slotPriorities = [$slots[] | { priority, slotId: .slot_id }]')\""
+
+    environment {
+      bot_id        = aws_lexv2models_bot.development_pre_survey.id
+      bot_version   = aws_lexv2models_bot_locale.development_pre_survey.bot_version
+      locale_id     = aws_lexv2models_bot_locale.development_pre_survey.locale_id
+      intent_id     = aws_lexv2models_intent.survey_start.intent_id
+      slot_priority = jsonencode(self.triggers_replace)
+    }
+  }
+}
@@ -0,0 +1,838 @@
+resource "aws_lexv2models_slot_type" "development_pre_survey_Age" {
+  bot_id      = aws_lexv2models_bot.development_pre_survey.id
+  bot_version = aws_lexv2models_bot_locale.development_pre_survey.bot_version
+  locale_id   = aws_lexv2models_bot_locale.development_pre_survey.locale_id
+  name        = "Age"
+
+  value_selection_setting {
+    resolution_strategy = "TopResolution"
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "100"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "99"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "98"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "97"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "96"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "95"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "94"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "93"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "92"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "91"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "90"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "89"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "88"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "87"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "86"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "85"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "84"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "83"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "82"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "81"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "80"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "79"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "78"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "77"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "76"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "75"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "74"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "73"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "72"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "71"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "70"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "69"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "68"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "67"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "66"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "65"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "64"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "63"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "62"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "61"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "60"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "59"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "58"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "57"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "56"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "55"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "54"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "53"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "52"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "51"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "50"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "49"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "48"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "47"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "46"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "45"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "44"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "43"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "42"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "41"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "40"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "39"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "38"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "37"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "36"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "35"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "34"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "33"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "32"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "31"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "30"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "29"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "28"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "27"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "26"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "25"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "24"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "23"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "22"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "21"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "20"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "19"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "18"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "17"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "16"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "15"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "14"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "13"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "12"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "11"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "10"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "9"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "8"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "7"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "6"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "5"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "4"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "Unknown"
+    }
+
+    synonyms {
+      value = "prefer not to"
+    }
+
+    synonyms {
+      value = "prefer not"
+    }
+
+    synonyms {
+      value = "X"
+    }
+
+    synonyms {
+      value = "Prefer not to answer"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "3"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "2"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "1"
+    }
+  }
+}
+
+resource "aws_lexv2models_slot_type" "development_pre_survey_Gender" {
+  bot_id      = aws_lexv2models_bot.development_pre_survey.id
+  bot_version = aws_lexv2models_bot_locale.development_pre_survey.bot_version
+  locale_id   = aws_lexv2models_bot_locale.development_pre_survey.locale_id
+  name        = "Gender"
+
+  value_selection_setting {
+    resolution_strategy = "TopResolution"
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "Non-Binary"
+    }
+
+    synonyms {
+      value = "non binary"
+    }
+
+    synonyms {
+      value = "agender"
+    }
+
+    synonyms {
+      value = "nonbinary"
+    }
+
+    synonyms {
+      value = "NB"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "Unknown"
+    }
+
+    synonyms {
+      value = "prefer not to"
+    }
+
+    synonyms {
+      value = "prefer not"
+    }
+
+    synonyms {
+      value = "none of your business"
+    }
+
+    synonyms {
+      value = "X"
+    }
+
+    synonyms {
+      value = "prefer not to answer"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "Girl"
+    }
+
+    synonyms {
+      value = "G"
+    }
+
+    synonyms {
+      value = "females"
+    }
+
+    synonyms {
+      value = "lady"
+    }
+
+    synonyms {
+      value = "female"
+    }
+
+    synonyms {
+      value = "F"
+    }
+
+    synonyms {
+      value = "W"
+    }
+
+    synonyms {
+      value = "woman"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "Boy"
+    }
+
+    synonyms {
+      value = "B"
+    }
+
+    synonyms {
+      value = "males"
+    }
+
+    synonyms {
+      value = "dude"
+    }
+
+    synonyms {
+      value = "guy"
+    }
+
+    synonyms {
+      value = "M"
+    }
+
+    synonyms {
+      value = "man"
+    }
+
+    synonyms {
+      value = "male"
+    }
+  }
+}
+
+resource "aws_lexv2models_slot_type" "development_pre_survey_YesNo" {
+  bot_id      = aws_lexv2models_bot.development_pre_survey.id
+  bot_version = aws_lexv2models_bot_locale.development_pre_survey.bot_version
+  locale_id   = aws_lexv2models_bot_locale.development_pre_survey.locale_id
+  name        = "YesNo"
+
+  value_selection_setting {
+    resolution_strategy = "TopResolution"
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "Yes"
+    }
+
+    synonyms {
+      value = "yeah"
+    }
+
+    synonyms {
+      value = "yep"
+    }
+
+    synonyms {
+      value = "yup"
+    }
+
+    synonyms {
+      value = "sure"
+    }
+
+    synonyms {
+      value = "ok"
+    }
+
+    synonyms {
+      value = "okay"
+    }
+
+    synonyms {
+      value = "correct"
+    }
+
+    synonyms {
+      value = "right"
+    }
+
+    synonyms {
+      value = "of course"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = "No"
+    }
+
+    synonyms {
+      value = "nope"
+    }
+
+    synonyms {
+      value = "nah"
+    }
+
+    synonyms {
+      value = "not really"
+    }
+
+    synonyms {
+      value = "incorrect"
+    }
+
+    synonyms {
+      value = "no way"
+    }
+  }
+}
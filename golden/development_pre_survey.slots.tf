@@ -0,0 +1,174 @@
+resource "aws_lexv2models_slot" "survey_age" {
+  bot_id       = aws_lexv2models_bot.development_pre_survey.id
+  bot_version  = aws_lexv2models_bot_locale.development_pre_survey.bot_version
+  locale_id    = aws_lexv2models_bot_locale.development_pre_survey.locale_id
+  intent_id    = aws_lexv2models_intent.survey.intent_id
+  name         = "age"
+  slot_type_id = aws_lexv2models_slot_type.development_pre_survey_Age.slot_type_id
+
+  value_elicitation_setting {
+    slot_constraint = "Required"
+
+    prompt_specification {
+      max_retries                = 1
+      message_selection_strategy = "Ordered"
+
+      message_group {
+        message {
+          plain_text_message {
+            value = "How old are you?"
+          }
+        }
+      }
+
+      message_group {
+        message {
+          plain_text_message {
+            value = "Sorry, I didn't understand that. Please respond with a number. How old are you?"
+          }
+        }
+      }
+    }
+
+    slot_capture_setting {
+      failure_next_step {
+        dialog_action {
+          type = "StartIntent"
+        }
+
+        intent {
+          name = "redirect_function"
+        }
+      }
+    }
+  }
+}
+
+resource "aws_lexv2models_slot" "survey_gender" {
+  bot_id       = aws_lexv2models_bot.development_pre_survey.id
+  bot_version  = aws_lexv2models_bot_locale.development_pre_survey.bot_version
+  locale_id    = aws_lexv2models_bot_locale.development_pre_survey.locale_id
+  intent_id    = aws_lexv2models_intent.survey.intent_id
+  name         = "gender"
+  slot_type_id = aws_lexv2models_slot_type.development_pre_survey_Gender.slot_type_id
+
+  value_elicitation_setting {
+    slot_constraint = "Required"
+
+    prompt_specification {
+      max_retries                = 1
+      message_selection_strategy = "Ordered"
+
+      message_group {
+        message {
+          plain_text_message {
+            value = "What is your gender?"
+          }
+        }
+      }
+
+      message_group {
+        message {
+          plain_text_message {
+            value = "Sorry, I didn't understand that. Please try again. What is your gender?"
+          }
+        }
+      }
+    }
+
+    slot_capture_setting {
+      failure_next_step {
+        dialog_action {
+          type = "StartIntent"
+        }
+
+        intent {
+          name = "redirect_function"
+        }
+      }
+    }
+  }
+}
+
+resource "aws_lexv2models_slot" "gender_why_gender" {
+  bot_id       = aws_lexv2models_bot.development_pre_survey.id
+  bot_version  = aws_lexv2models_bot_locale.development_pre_survey.bot_version
+  locale_id    = aws_lexv2models_bot_locale.development_pre_survey.locale_id
+  intent_id    = aws_lexv2models_intent.gender_why.intent_id
+  name         = "gender"
+  slot_type_id = aws_lexv2models_slot_type.development_pre_survey_Gender.slot_type_id
+
+  value_elicitation_setting {
+    slot_constraint = "Required"
+
+    prompt_specification {
+      max_retries = 0
+
+      message_group {
+        message {
+          plain_text_message {
+            value = "We ask for gender--whether you identify as a boy, girl, or neither--to help understand who is using our helpline. If you're uncomfortable answering, just say 'prefer not to answer.'"
+          }
+        }
+      }
+    }
+
+    slot_capture_setting {
+      failure_next_step {
+        dialog_action {
+          type = "StartIntent"
+        }
+
+        intent {
+          name = "redirect_function"
+        }
+      }
+    }
+  }
+}
+
+resource "aws_lexv2models_slot" "survey_start_about_self" {
+  bot_id       = aws_lexv2models_bot.development_pre_survey.id
+  bot_version  = aws_lexv2models_bot_locale.development_pre_survey.bot_version
+  locale_id    = aws_lexv2models_bot_locale.development_pre_survey.locale_id
+  intent_id    = aws_lexv2models_intent.survey_start.intent_id
+  name         = "about_self"
+  slot_type_id = aws_lexv2models_slot_type.development_pre_survey_YesNo.slot_type_id
+
+  value_elicitation_setting {
+    slot_constraint = "Required"
+
+    prompt_specification {
+      max_retries                = 1
+      message_selection_strategy = "Ordered"
+
+      message_group {
+        message {
+          plain_text_message {
+            value = "Are you calling about yourself? Please answer Yes or No."
+          }
+        }
+      }
+
+      message_group {
+        message {
+          plain_text_message {
+            value = "Sorry, I didn't understand that. Are you calling about yourself? Please answer Yes or No."
+          }
+        }
+      }
+    }
+
+    slot_capture_setting {
+      failure_next_step {
+        dialog_action {
+          type = "StartIntent"
+        }
+
+        intent {
+          name = "redirect_function"
+        }
+      }
+    }
+  }
+}
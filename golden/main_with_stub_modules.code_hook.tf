@@ -0,0 +1,12 @@
+resource "aws_lambda_function" "pre_survey" {
+  function_name    = "demo_chatbot-code-hook"
+  role             = var.lambda_role_arn
+  runtime          = "nodejs18.x"
+  handler          = "lex_code_hook.handler"
+  filename         = data.archive_file.lex_code_hook.output_path
+  source_code_hash = data.archive_file.lex_code_hook.output_base64sha256
+
+  environment {
+    variables = { ROUTES = jsonencode({ redirect_function = { url = "${var.serverless_url}/autopilotRedirect", method = "POST" } }) }
+  }
+}
@@ -0,0 +1,289 @@
+// No samples, the intent is only reachable through dialog transitions
+resource "aws_lexv2models_intent" "redirect_function" {
+  bot_id      = aws_lexv2models_bot.pre_survey.id
+  bot_version = aws_lexv2models_bot_locale.pre_survey.bot_version
+  locale_id   = aws_lexv2models_bot_locale.pre_survey.locale_id
+  name        = "redirect_function"
+
+  fulfillment_code_hook {
+    enabled = true
+  }
+}
+
+// No samples, the intent is only reachable through dialog transitions
+resource "aws_lexv2models_intent" "gender_why" {
+  bot_id      = aws_lexv2models_bot.pre_survey.id
+  bot_version = aws_lexv2models_bot_locale.pre_survey.bot_version
+  locale_id   = aws_lexv2models_bot_locale.pre_survey.locale_id
+  name        = "gender_why"
+
+  closing_setting {
+    active = true
+
+    next_step {
+      session_attributes = { at = "gender_why" }
+
+      dialog_action {
+        type = "StartIntent"
+      }
+
+      intent {
+        name = "redirect_function"
+      }
+    }
+  }
+
+  lifecycle {
+    ignore_changes = [slot_priority]
+  }
+}
+
+// No samples, the intent is only reachable through dialog transitions
+resource "aws_lexv2models_intent" "survey_start" {
+  bot_id      = aws_lexv2models_bot.pre_survey.id
+  bot_version = aws_lexv2models_bot_locale.pre_survey.bot_version
+  locale_id   = aws_lexv2models_bot_locale.pre_survey.locale_id
+  name        = "survey_start"
+
+  closing_setting {
+    active = true
+
+    next_step {
+      session_attributes = { at = "survey_start" }
+
+      dialog_action {
+        type = "StartIntent"
+      }
+
+      intent {
+        name = "survey"
+      }
+    }
+  }
+
+  lifecycle {
+    ignore_changes = [slot_priority]
+  }
+}
+
+// No samples, the intent is only reachable through dialog transitions
+resource "aws_lexv2models_intent" "counselor_handoff" {
+  bot_id      = aws_lexv2models_bot.pre_survey.id
+  bot_version = aws_lexv2models_bot_locale.pre_survey.bot_version
+  locale_id   = aws_lexv2models_bot_locale.pre_survey.locale_id
+  name        = "counselor_handoff"
+
+  closing_setting {
+    active = true
+
+    next_step {
+      session_attributes = { at = "counselor_handoff", sendToAgent = jsonencode(true) }
+
+      dialog_action {
+        type = "ElicitIntent"
+      }
+    }
+  }
+}
+
+resource "aws_lexv2models_intent" "fallback" {
+  bot_id                  = aws_lexv2models_bot.pre_survey.id
+  bot_version             = aws_lexv2models_bot_locale.pre_survey.bot_version
+  locale_id               = aws_lexv2models_bot_locale.pre_survey.locale_id
+  name                    = "fallback"
+  parent_intent_signature = "AMAZON.FallbackIntent"
+}
+
+resource "aws_lexv2models_intent" "greeting" {
+  bot_id      = aws_lexv2models_bot.pre_survey.id
+  bot_version = aws_lexv2models_bot_locale.pre_survey.bot_version
+  locale_id   = aws_lexv2models_bot_locale.pre_survey.locale_id
+  name        = "greeting"
+
+  sample_utterance {
+    utterance = "hi!"
+  }
+
+  sample_utterance {
+    utterance = "hi there"
+  }
+
+  sample_utterance {
+    utterance = "good afternoon"
+  }
+
+  sample_utterance {
+    utterance = "good morning"
+  }
+
+  sample_utterance {
+    utterance = "heya"
+  }
+
+  sample_utterance {
+    utterance = "Hi"
+  }
+
+  sample_utterance {
+    utterance = "Hello."
+  }
+
+  sample_utterance {
+    utterance = "hey"
+  }
+
+  sample_utterance {
+    utterance = "hi there."
+  }
+
+  sample_utterance {
+    utterance = "hello"
+  }
+
+  sample_utterance {
+    utterance = "what'us up"
+  }
+
+  sample_utterance {
+    utterance = "what do you do"
+  }
+
+  sample_utterance {
+    utterance = "what can you do"
+  }
+
+  sample_utterance {
+    utterance = "whatsup"
+  }
+
+  sample_utterance {
+    utterance = "sup"
+  }
+
+  sample_utterance {
+    utterance = "yo"
+  }
+
+  closing_setting {
+    active = true
+
+    next_step {
+      session_attributes = { at = "greeting" }
+
+      dialog_action {
+        type = "StartIntent"
+      }
+
+      intent {
+        name = "survey_start"
+      }
+    }
+  }
+}
+
+// No samples, the intent is only reachable through dialog transitions
+resource "aws_lexv2models_intent" "collect_fallback" {
+  bot_id      = aws_lexv2models_bot.pre_survey.id
+  bot_version = aws_lexv2models_bot_locale.pre_survey.bot_version
+  locale_id   = aws_lexv2models_bot_locale.pre_survey.locale_id
+  name        = "collect_fallback"
+}
+
+resource "aws_lexv2models_intent" "goodbye" {
+  bot_id      = aws_lexv2models_bot.pre_survey.id
+  bot_version = aws_lexv2models_bot_locale.pre_survey.bot_version
+  locale_id   = aws_lexv2models_bot_locale.pre_survey.locale_id
+  name        = "goodbye"
+
+  sample_utterance {
+    utterance = "no thanks"
+  }
+
+  sample_utterance {
+    utterance = "that is all thank you"
+  }
+
+  sample_utterance {
+    utterance = "that's all for today"
+  }
+
+  sample_utterance {
+    utterance = "go away"
+  }
+
+  sample_utterance {
+    utterance = "that would be all thanks"
+  }
+
+  sample_utterance {
+    utterance = "no"
+  }
+
+  sample_utterance {
+    utterance = "that would be all"
+  }
+
+  sample_utterance {
+    utterance = "goodbye"
+  }
+
+  sample_utterance {
+    utterance = "goodnight"
+  }
+
+  sample_utterance {
+    utterance = "cancel"
+  }
+
+  sample_utterance {
+    utterance = "good bye"
+  }
+
+  sample_utterance {
+    utterance = "stop talking"
+  }
+
+  sample_utterance {
+    utterance = "stop"
+  }
+
+  sample_utterance {
+    utterance = "see ya"
+  }
+
+  sample_utterance {
+    utterance = "bye bye"
+  }
+
+  sample_utterance {
+    utterance = "that's all"
+  }
+}
+
+// No samples, the intent is only reachable through dialog transitions
+resource "aws_lexv2models_intent" "default_pre_survey_task_0_survey" {
+  bot_id      = aws_lexv2models_bot.pre_survey.id
+  bot_version = aws_lexv2models_bot_locale.pre_survey.bot_version
+  locale_id   = aws_lexv2models_bot_locale.pre_survey.locale_id
+  name        = "survey"
+
+  closing_setting {
+    active = true
+
+    next_step {
+      session_attributes = { at = "survey" }
+
+      dialog_action {
+        type = "StartIntent"
+      }
+
+      intent {
+        name = "redirect_function"
+      }
+    }
+  }
+
+  lifecycle {
+    ignore_changes = [slot_priority]
+  }
+}
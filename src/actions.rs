@@ -0,0 +1,693 @@
+use crate::{autopilot::Text, json_utils::JsonValue};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
+
+/// An Autopilot action, one of the items of a task's `{ "actions": [...] }`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    Say(Text),
+    Listen(Listen),
+    Collect(Collect),
+    Redirect(Redirect),
+    /// Values kept in the memory of the conversation
+    Remember(BTreeMap<String, JsonValue>),
+    Handoff(Handoff),
+    Show(Show),
+    Play(Play),
+    Prompt(Text),
+}
+
+/// `"listen": true`, or `"listen": { "tasks": [...] }` to only listen for some tasks
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Listen {
+    pub tasks: Vec<String>,
+    /// The keys Lex has no equivalent of, by path, e.g. `actions[2].listen.voice_digits`
+    pub ignored: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Collect {
+    pub name: String,
+    pub questions: Vec<Question>,
+    pub on_complete: Option<Redirect>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Question {
+    pub name: String,
+    pub question: Text,
+    /// The unique name of a field type, or a built-in one like `Twilio.YES_NO`
+    pub field_type: Option<String>,
+    pub validate: Option<Validate>,
+    pub prefill: Option<String>,
+    /// The keys Lex has no equivalent of, by path, e.g. `actions[2].listen.voice_digits`
+    pub ignored: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Validate {
+    pub allowed_values: Vec<String>,
+    pub on_failure: Option<OnFailure>,
+    pub on_success: Option<Text>,
+    pub max_attempts: Option<MaxAttempts>,
+    pub webhook: Option<Webhook>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct OnFailure {
+    /// Said on each failed attempt, in order
+    pub messages: Vec<Text>,
+    pub repeat_question: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaxAttempts {
+    pub num_attempts: u32,
+    /// Where the conversation goes once the attempts run out
+    pub redirect: Option<Redirect>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Webhook {
+    pub url: Text,
+    pub method: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Redirect {
+    /// `task://unique_name`
+    Task(String),
+    /// A webhook answering with the next actions
+    Url { uri: Text, method: Option<String> },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Handoff {
+    pub channel: String,
+    pub uri: Option<Text>,
+    pub method: Option<String>,
+    /// The keys Lex has no equivalent of, by path, e.g. `actions[2].listen.voice_digits`
+    pub ignored: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Show {
+    pub body: Text,
+    pub images: Vec<Image>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    pub label: Option<String>,
+    pub url: Text,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Play {
+    pub url: Text,
+    pub loop_count: Option<u32>,
+    pub digits: Option<String>,
+}
+
+/// What is wrong with the actions of a task, and where, e.g. `actions[1].collect.questions[0]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActionError {
+    pub path: String,
+    pub kind: ActionErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ActionErrorKind {
+    UnknownAction(String),
+    UnknownKey(String),
+    MissingKey(&'static str),
+    Mistyped(&'static str),
+}
+
+impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.path.is_empty() {
+            write!(f, "{}: ", self.path)?;
+        }
+        match &self.kind {
+            ActionErrorKind::UnknownAction(name) => write!(f, "unknown action \"{}\"", name),
+            ActionErrorKind::UnknownKey(key) => write!(f, "unknown key \"{}\"", key),
+            ActionErrorKind::MissingKey(key) => write!(f, "missing key \"{}\"", key),
+            ActionErrorKind::Mistyped(expected) => write!(f, "should be {}", expected),
+        }
+    }
+}
+
+impl std::error::Error for ActionError {}
+
+/// Parses the `{ "actions": [...] }` document of a task
+pub fn parse_actions(json: &JsonValue) -> Result<Vec<Action>, ActionError> {
+    let root = Value::root(json);
+    let document = root.object(&["actions"])?;
+
+    document
+        .required("actions")?
+        .items()?
+        .iter()
+        .filter_map(|value| parse_action(value).transpose())
+        .collect()
+}
+
+/// The action of an item of `actions`, `None` for an action doing nothing like `"listen": false`
+fn parse_action(value: &Value) -> Result<Option<Action>, ActionError> {
+    let object = value.as_object()?;
+    let (name, body) = match object.entries.iter().collect::<Vec<_>>().as_slice() {
+        [(name, body)] => (name.as_str(), value.at(name, body)),
+        _ => return Err(value.error(ActionErrorKind::Mistyped("an object with a single action"))),
+    };
+
+    let action = match name {
+        "say" => Action::Say(say(&body)?),
+        "listen" => match body.json {
+            JsonValue::Boolean(true) => Action::Listen(Listen::default()),
+            JsonValue::Boolean(false) => return Ok(None),
+            _ => {
+                let listen = body.object(&["tasks", "voice_digits"])?;
+                Action::Listen(Listen {
+                    tasks: listen.strings("tasks")?,
+                    ignored: listen.present(&["voice_digits"]),
+                })
+            }
+        },
+        "collect" => {
+            let collect = body.object(&["name", "questions", "on_complete"])?;
+            Action::Collect(Collect {
+                name: collect.required("name")?.string()?,
+                questions: collect
+                    .required("questions")?
+                    .items()?
+                    .iter()
+                    .map(question)
+                    .collect::<Result<_, _>>()?,
+                on_complete: match collect.get("on_complete") {
+                    Some(on_complete) => Some(
+                        on_complete
+                            .object(&["redirect"])?
+                            .required("redirect")?
+                            .redirect()?,
+                    ),
+                    None => None,
+                },
+            })
+        }
+        "redirect" => Action::Redirect(body.redirect()?),
+        "remember" => Action::Remember(
+            body.as_object()?
+                .entries
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+        ),
+        "handoff" => {
+            const IGNORED: &[&str] = &[
+                "wait_url",
+                "wait_url_method",
+                "action",
+                "priority",
+                "timeout",
+                "attributes",
+            ];
+            let handoff = body.object(&[&["channel", "uri", "method"], IGNORED].concat())?;
+            Action::Handoff(Handoff {
+                channel: handoff.required("channel")?.string()?,
+                uri: handoff.get("uri").map(|u| u.text()).transpose()?,
+                method: handoff.get("method").map(|m| m.string()).transpose()?,
+                ignored: handoff.present(IGNORED),
+            })
+        }
+        "show" => {
+            let show = body.object(&["body", "images"])?;
+            Action::Show(Show {
+                body: show.required("body")?.text()?,
+                images: match show.get("images") {
+                    Some(images) => images
+                        .items()?
+                        .iter()
+                        .map(|image| {
+                            let image = image.object(&["label", "url"])?;
+                            Ok(Image {
+                                label: image.get("label").map(|l| l.string()).transpose()?,
+                                url: image.required("url")?.text()?,
+                            })
+                        })
+                        .collect::<Result<_, _>>()?,
+                    None => vec![],
+                },
+            })
+        }
+        "play" => {
+            let play = body.object(&["url", "loop", "digits"])?;
+            Action::Play(Play {
+                url: play.required("url")?.text()?,
+                loop_count: play.get("loop").map(|l| l.count()).transpose()?,
+                digits: play.get("digits").map(|d| d.string()).transpose()?,
+            })
+        }
+        "prompt" => Action::Prompt(say(&body)?),
+        name => return Err(value.error(ActionErrorKind::UnknownAction(name.to_string()))),
+    };
+
+    Ok(Some(action))
+}
+
+/// A text to say, either `"..."` or `{ "speech": "..." }` as in style sheets
+fn say(value: &Value) -> Result<Text, ActionError> {
+    match value.json {
+        JsonValue::Object(_) => value.object(&["speech"])?.required("speech")?.text(),
+        _ => value.text(),
+    }
+}
+
+fn question(value: &Value) -> Result<Question, ActionError> {
+    let question = value.object(&[
+        "name",
+        "question",
+        "type",
+        "validate",
+        "prefill",
+        "voice_digits",
+    ])?;
+
+    Ok(Question {
+        name: question.required("name")?.string()?,
+        question: say(&question.required("question")?)?,
+        field_type: question.get("type").map(|t| t.string()).transpose()?,
+        validate: question.get("validate").map(|v| validate(&v)).transpose()?,
+        prefill: question.get("prefill").map(|p| p.string()).transpose()?,
+        ignored: question.present(&["voice_digits"]),
+    })
+}
+
+fn validate(value: &Value) -> Result<Validate, ActionError> {
+    let validate = value.object(&[
+        "allowed_values",
+        "on_failure",
+        "on_success",
+        "max_attempts",
+        "webhook",
+    ])?;
+
+    let on_failure = match validate.get("on_failure") {
+        Some(on_failure) => {
+            let on_failure = on_failure.object(&["messages", "repeat_question"])?;
+            Some(OnFailure {
+                messages: match on_failure.get("messages") {
+                    Some(messages) => messages
+                        .items()?
+                        .iter()
+                        .map(|m| say(&m.object(&["say"])?.required("say")?))
+                        .collect::<Result<_, _>>()?,
+                    None => vec![],
+                },
+                repeat_question: match on_failure.get("repeat_question") {
+                    Some(repeat) => repeat.boolean()?,
+                    None => false,
+                },
+            })
+        }
+        None => None,
+    };
+
+    let max_attempts = match validate.get("max_attempts") {
+        Some(max_attempts) => {
+            let max_attempts = max_attempts.object(&["num_attempts", "redirect"])?;
+            Some(MaxAttempts {
+                num_attempts: max_attempts.required("num_attempts")?.count()?,
+                redirect: max_attempts
+                    .get("redirect")
+                    .map(|r| r.redirect())
+                    .transpose()?,
+            })
+        }
+        None => None,
+    };
+
+    Ok(Validate {
+        allowed_values: match validate.get("allowed_values") {
+            Some(allowed) => allowed.object(&["list"])?.strings("list")?,
+            None => vec![],
+        },
+        on_failure,
+        on_success: match validate.get("on_success") {
+            Some(on_success) => Some(say(&on_success.object(&["say"])?.required("say")?)?),
+            None => None,
+        },
+        max_attempts,
+        webhook: match validate.get("webhook") {
+            Some(webhook) => {
+                let webhook = webhook.object(&["url", "method"])?;
+                Some(Webhook {
+                    url: webhook.required("url")?.text()?,
+                    method: webhook.get("method").map(|m| m.string()).transpose()?,
+                })
+            }
+            None => None,
+        },
+    })
+}
+
+fn child_path(path: &str, key: &str) -> String {
+    match path.is_empty() {
+        true => key.to_string(),
+        false => format!("{}.{}", path, key),
+    }
+}
+
+/// A JSON value and where it is in the document, to report errors precisely
+struct Value<'a> {
+    json: &'a JsonValue,
+    path: String,
+}
+
+struct Object<'a> {
+    entries: &'a HashMap<String, JsonValue>,
+    path: String,
+}
+
+impl<'a> Value<'a> {
+    fn root(json: &'a JsonValue) -> Self {
+        Value {
+            json,
+            path: String::new(),
+        }
+    }
+
+    fn at(&self, key: &str, json: &'a JsonValue) -> Self {
+        Value {
+            json,
+            path: child_path(&self.path, key),
+        }
+    }
+
+    fn error(&self, kind: ActionErrorKind) -> ActionError {
+        ActionError {
+            path: self.path.clone(),
+            kind,
+        }
+    }
+
+    fn mistyped(&self, expected: &'static str) -> ActionError {
+        self.error(ActionErrorKind::Mistyped(expected))
+    }
+
+    fn as_object(&self) -> Result<Object<'a>, ActionError> {
+        match self.json {
+            JsonValue::Object(entries) => Ok(Object {
+                entries,
+                path: self.path.clone(),
+            }),
+            _ => Err(self.mistyped("an object")),
+        }
+    }
+
+    /// An object whose keys are all in `keys`
+    fn object(&self, keys: &[&str]) -> Result<Object<'a>, ActionError> {
+        let object = self.as_object()?;
+
+        // Sorted so the same document always reports the same key
+        let mut unknown: Vec<&String> = object
+            .entries
+            .keys()
+            .filter(|k| !keys.contains(&k.as_str()))
+            .collect();
+        unknown.sort();
+        match unknown.first() {
+            Some(key) => Err(self.error(ActionErrorKind::UnknownKey(key.to_string()))),
+            None => Ok(object),
+        }
+    }
+
+    fn items(&self) -> Result<Vec<Value<'a>>, ActionError> {
+        match self.json {
+            JsonValue::Array(items) => Ok(items
+                .iter()
+                .enumerate()
+                .map(|(i, json)| Value {
+                    json,
+                    path: format!("{}[{}]", self.path, i),
+                })
+                .collect()),
+            _ => Err(self.mistyped("an array")),
+        }
+    }
+
+    fn string(&self) -> Result<String, ActionError> {
+        self.json
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| self.mistyped("a string"))
+    }
+
+    fn text(&self) -> Result<Text, ActionError> {
+        Text::from_json(self.json).ok_or_else(|| self.mistyped("a string"))
+    }
+
+    fn boolean(&self) -> Result<bool, ActionError> {
+        match self.json {
+            JsonValue::Boolean(b) => Ok(*b),
+            _ => Err(self.mistyped("a boolean")),
+        }
+    }
+
+    fn count(&self) -> Result<u32, ActionError> {
+        match self.json {
+            JsonValue::Num(n) if *n >= 0.0 && n.fract() == 0.0 => Ok(*n as u32),
+            _ => Err(self.mistyped("a positive integer")),
+        }
+    }
+
+    /// `"task://name"`, `"https://..."` or `{ "uri": "...", "method": "POST" }`
+    fn redirect(&self) -> Result<Redirect, ActionError> {
+        let (uri, method) = match self.json {
+            JsonValue::Object(_) => {
+                let redirect = self.object(&["uri", "method"])?;
+                let method = redirect.get("method").map(|m| m.string()).transpose()?;
+                (redirect.required("uri")?.text()?, method)
+            }
+            _ => (self.text()?, None),
+        };
+
+        match &uri {
+            Text::Literal(uri) if uri.starts_with("task://") => Ok(Redirect::Task(
+                uri.trim_start_matches("task://").to_string(),
+            )),
+            _ => Ok(Redirect::Url { uri, method }),
+        }
+    }
+}
+
+impl<'a> Object<'a> {
+    fn get(&self, key: &str) -> Option<Value<'a>> {
+        self.entries.get_key_value(key).map(|(key, json)| Value {
+            json,
+            path: child_path(&self.path, key),
+        })
+    }
+
+    fn required(&self, key: &'static str) -> Result<Value<'a>, ActionError> {
+        self.get(key).ok_or_else(|| ActionError {
+            path: self.path.clone(),
+            kind: ActionErrorKind::MissingKey(key),
+        })
+    }
+
+    /// The paths of those of `keys` the object has
+    fn present(&self, keys: &[&str]) -> Vec<String> {
+        keys.iter()
+            .filter(|key| self.entries.contains_key(**key))
+            .map(|key| child_path(&self.path, key))
+            .collect()
+    }
+
+    fn strings(&self, key: &'static str) -> Result<Vec<String>, ActionError> {
+        match self.get(key) {
+            Some(list) => list.items()?.iter().map(Value::string).collect(),
+            None => Ok(vec![]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ast::TemplatePart, json_utils::root};
+    use nom::error::ErrorKind;
+
+    fn actions_of(json: &str) -> Result<Vec<Action>, ActionError> {
+        let (_, json) = root::<(&str, ErrorKind)>(json).expect("Failed parsing the JSON");
+        parse_actions(&json)
+    }
+
+    #[test]
+    fn parse_every_action() {
+        let actions = actions_of(
+            r#"{ "actions": [
+                { "remember": { "at": "survey" } },
+                { "say": "Hi ${var.name}" },
+                { "collect": {
+                    "name": "collect_survey",
+                    "questions": [{
+                        "name": "age",
+                        "question": "How old are you?",
+                        "type": "Age",
+                        "validate": {
+                            "on_failure": { "repeat_question": true, "messages": [{ "say": "Sorry" }] },
+                            "max_attempts": { "redirect": "task://fallback", "num_attempts": 2 },
+                            "allowed_values": { "list": ["1", "2"] }
+                        }
+                    }],
+                    "on_complete": { "redirect": { "uri": "https://example.com", "method": "POST" } }
+                } },
+                { "listen": true },
+                { "handoff": { "channel": "voice", "uri": "https://example.com/handoff" } },
+                { "show": { "body": "Look", "images": [{ "label": "a", "url": "https://example.com/a.png" }] } },
+                { "play": { "url": "https://example.com/a.mp3", "loop": 2 } },
+                { "prompt": "Anything else?" },
+                { "redirect": "task://goodbye" }
+            ] }"#,
+        )
+        .unwrap();
+
+        assert_eq!(actions.len(), 9);
+        assert_eq!(
+            actions[0],
+            Action::Remember(BTreeMap::from([(
+                "at".to_string(),
+                JsonValue::Str("survey".to_string())
+            )]))
+        );
+        assert_eq!(
+            actions[1],
+            Action::Say(Text::Template(vec![
+                TemplatePart::Literal("Hi ".to_string()),
+                TemplatePart::Interpolation(crate::ast::Expression::traversal("var", &["name"])),
+            ]))
+        );
+        assert_eq!(
+            actions[2],
+            Action::Collect(Collect {
+                name: "collect_survey".to_string(),
+                questions: vec![Question {
+                    name: "age".to_string(),
+                    question: Text::Literal("How old are you?".to_string()),
+                    field_type: Some("Age".to_string()),
+                    validate: Some(Validate {
+                        allowed_values: vec!["1".to_string(), "2".to_string()],
+                        on_failure: Some(OnFailure {
+                            messages: vec![Text::Literal("Sorry".to_string())],
+                            repeat_question: true,
+                        }),
+                        on_success: None,
+                        max_attempts: Some(MaxAttempts {
+                            num_attempts: 2,
+                            redirect: Some(Redirect::Task("fallback".to_string())),
+                        }),
+                        webhook: None,
+                    }),
+                    prefill: None,
+                    ignored: vec![],
+                }],
+                on_complete: Some(Redirect::Url {
+                    uri: Text::Literal("https://example.com".to_string()),
+                    method: Some("POST".to_string()),
+                }),
+            })
+        );
+        assert_eq!(actions[3], Action::Listen(Listen::default()));
+        assert_eq!(
+            actions[8],
+            Action::Redirect(Redirect::Task("goodbye".to_string()))
+        );
+    }
+
+    #[test]
+    fn ignore_keys_without_equivalent() {
+        let actions = actions_of(
+            r##"{ "actions": [
+                { "collect": { "name": "c", "questions": [
+                    { "name": "pin", "question": "Your PIN?", "voice_digits": { "num_digits": 4 } }
+                ] } },
+                { "listen": { "tasks": ["pin"], "voice_digits": { "finish_on_key": "#" } } },
+                { "handoff": {
+                    "channel": "voice",
+                    "uri": "https://example.com/handoff",
+                    "wait_url": "https://example.com/wait",
+                    "wait_url_method": "GET",
+                    "action": "https://example.com/done",
+                    "priority": 1,
+                    "timeout": 60,
+                    "attributes": { "team": "support" }
+                } }
+            ] }"##,
+        )
+        .unwrap();
+
+        let ignored: Vec<&String> = actions
+            .iter()
+            .flat_map(|action| match action {
+                Action::Collect(collect) => collect.questions[0].ignored.iter().collect(),
+                Action::Listen(listen) => listen.ignored.iter().collect(),
+                Action::Handoff(handoff) => handoff.ignored.iter().collect(),
+                _ => vec![],
+            })
+            .collect();
+        assert_eq!(
+            ignored,
+            [
+                "actions[0].collect.questions[0].voice_digits",
+                "actions[1].listen.voice_digits",
+                "actions[2].handoff.wait_url",
+                "actions[2].handoff.wait_url_method",
+                "actions[2].handoff.action",
+                "actions[2].handoff.priority",
+                "actions[2].handoff.timeout",
+                "actions[2].handoff.attributes",
+            ]
+        );
+    }
+
+    #[test]
+    fn skip_not_listening() {
+        let actions = actions_of(r#"{ "actions": [{ "say": "Bye" }, { "listen": false }] }"#);
+        assert_eq!(
+            actions,
+            Ok(vec![Action::Say(Text::Literal("Bye".to_string()))])
+        );
+    }
+
+    #[test]
+    fn report_unknown_keys() {
+        let error = actions_of(
+            r#"{ "actions": [
+                { "say": "Hi" },
+                { "collect": { "name": "c", "questions": [{ "name": "age", "question": "Age?", "kind": "Age" }] } }
+            ] }"#,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "actions[1].collect.questions[0]: unknown key \"kind\""
+        );
+    }
+
+    #[test]
+    fn report_unknown_actions() {
+        let error = actions_of(r#"{ "actions": [{ "dance": true }] }"#).unwrap_err();
+        assert_eq!(error.to_string(), "actions[0]: unknown action \"dance\"");
+
+        let error =
+            actions_of(r#"{ "actions": [{ "collect": { "questions": [] } }] }"#).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "actions[0].collect: missing key \"name\""
+        );
+    }
+}
@@ -1,23 +1,326 @@
 use crate::json_utils::JsonValue;
+use std::fmt;
 
-#[derive(Debug, PartialEq)]
-pub enum ResourcePropertyValue {
-    VNull,
-    VBoolean(bool),
-    VString(String),
-    VJson(JsonValue),
-    VSet(Vec<String>),
-    VEach,
-    VReference { res_type: String, res_name: String },
+/// An HCL expression, e.g. the value of a resource property
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Null,
+    Boolean(bool),
+    Number(f64),
+    /// A string without interpolations
+    String(String),
+    /// A string (or heredoc) with `${...}` interpolations
+    Template(Vec<TemplatePart>),
+    /// The literal JSON passed to `jsonencode(...)`
+    Json(JsonValue),
+    Tuple(Vec<Expression>),
+    /// Bare identifiers used as keys are kept as `Expression::String`s
+    Object(Vec<(Expression, Expression)>),
+    /// A bare name, e.g. `var`, `each` or a resource type
+    Identifier(String),
+    /// `root.attr[index].*.attr` and the like
+    Traversal(Box<Expression>, Vec<TraversalStep>),
+    FunctionCall {
+        name: String,
+        args: Vec<Expression>,
+        /// Whether the last argument is expanded with `...`
+        expand_final: bool,
+    },
+    Unary(UnaryOperator, Box<Expression>),
+    Binary(BinaryOperator, Box<Expression>, Box<Expression>),
+    Conditional {
+        condition: Box<Expression>,
+        then: Box<Expression>,
+        otherwise: Box<Expression>,
+    },
+    For(Box<ForExpression>),
+    Parenthesized(Box<Expression>),
 }
 
-pub type ResourceProperty = (String, ResourcePropertyValue);
+#[derive(Debug, Clone, PartialEq)]
+pub enum TraversalStep {
+    /// `.name`
+    GetAttr(String),
+    /// `[key]`, or the legacy `.0`
+    Index(Expression),
+    /// `.*`, applies the following attribute accesses to every element
+    AttrSplat,
+    /// `[*]`, applies the following steps to every element
+    FullSplat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnaryOperator {
+    Not,
+    Negate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOperator {
+    Or,
+    And,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+}
+
+impl BinaryOperator {
+    /// Higher binds tighter, e.g. `*` over `+`
+    pub fn precedence(self) -> u8 {
+        match self {
+            BinaryOperator::Or => 1,
+            BinaryOperator::And => 2,
+            BinaryOperator::Equal | BinaryOperator::NotEqual => 3,
+            BinaryOperator::Less
+            | BinaryOperator::LessOrEqual
+            | BinaryOperator::Greater
+            | BinaryOperator::GreaterOrEqual => 4,
+            BinaryOperator::Add | BinaryOperator::Subtract => 5,
+            BinaryOperator::Multiply | BinaryOperator::Divide | BinaryOperator::Modulo => 6,
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BinaryOperator::Or => "||",
+            BinaryOperator::And => "&&",
+            BinaryOperator::Equal => "==",
+            BinaryOperator::NotEqual => "!=",
+            BinaryOperator::Less => "<",
+            BinaryOperator::LessOrEqual => "<=",
+            BinaryOperator::Greater => ">",
+            BinaryOperator::GreaterOrEqual => ">=",
+            BinaryOperator::Add => "+",
+            BinaryOperator::Subtract => "-",
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::Divide => "/",
+            BinaryOperator::Modulo => "%",
+        }
+    }
+}
+
+/// `[for k, v in collection : value if condition]`, or `{for k, v in collection : key => value...}`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForExpression {
+    pub key_var: Option<String>,
+    pub value_var: String,
+    pub collection: Expression,
+    /// Only set when building an object
+    pub key: Option<Expression>,
+    pub value: Expression,
+    pub condition: Option<Expression>,
+    /// `...` after the value of an object `for`, grouping the values with the same key
+    pub grouping: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplatePart {
+    Literal(String),
+    Interpolation(Expression),
+}
+
+impl Expression {
+    /// A reference like `root.attr.attr`, e.g. `var.name` or `twilio_autopilot_assistants_v1.survey.sid`
+    #[cfg(test)]
+    pub fn traversal(root: &str, attrs: &[&str]) -> Self {
+        Expression::Traversal(
+            Box::new(Expression::Identifier(root.to_string())),
+            attrs
+                .iter()
+                .map(|a| TraversalStep::GetAttr(a.to_string()))
+                .collect(),
+        )
+    }
+
+    /// The expressions directly nested in this one, including the interpolations in JSON strings
+    pub fn children(&self) -> Vec<&Expression> {
+        let mut children: Vec<&Expression> = vec![];
+        match self {
+            Expression::Template(parts) => children.extend(parts.iter().filter_map(|p| match p {
+                TemplatePart::Interpolation(e) => Some(e),
+                TemplatePart::Literal(_) => None,
+            })),
+            Expression::Json(json) => json.walk_templates(&mut |parts| {
+                children.extend(parts.iter().filter_map(|p| match p {
+                    TemplatePart::Interpolation(e) => Some(e),
+                    TemplatePart::Literal(_) => None,
+                }))
+            }),
+            Expression::Tuple(items) => children.extend(items),
+            Expression::Object(items) => children.extend(items.iter().flat_map(|(k, v)| [k, v])),
+            Expression::Traversal(root, steps) => {
+                children.push(root);
+                children.extend(steps.iter().filter_map(|s| match s {
+                    TraversalStep::Index(e) => Some(e),
+                    _ => None,
+                }));
+            }
+            Expression::FunctionCall { args, .. } => children.extend(args),
+            Expression::Unary(_, e) | Expression::Parenthesized(e) => children.push(e),
+            Expression::Binary(_, lhs, rhs) => children.extend([lhs.as_ref(), rhs.as_ref()]),
+            Expression::Conditional {
+                condition,
+                then,
+                otherwise,
+            } => children.extend([condition.as_ref(), then.as_ref(), otherwise.as_ref()]),
+            Expression::For(f) => {
+                children.extend([&f.collection, &f.value]);
+                children.extend(f.key.iter().chain(f.condition.iter()));
+            }
+            Expression::Null
+            | Expression::Boolean(_)
+            | Expression::Number(_)
+            | Expression::String(_)
+            | Expression::Identifier(_) => {}
+        }
+        children
+    }
+
+    /// Every sub expression, this one included, depth first
+    pub fn walk<'a>(&'a self, visit: &mut impl FnMut(&'a Expression)) {
+        visit(self);
+        for child in self.children() {
+            child.walk(visit);
+        }
+    }
+
+    /// The names of the variables used in this expression
+    pub fn variables(&self) -> Vec<&str> {
+        let mut variables = vec![];
+        self.walk(&mut |e| {
+            if let Expression::Traversal(root, steps) = e {
+                if let (Expression::Identifier(root), Some(TraversalStep::GetAttr(name))) =
+                    (root.as_ref(), steps.first())
+                {
+                    if root == "var" {
+                        variables.push(name.as_str());
+                    }
+                }
+            }
+        });
+        variables
+    }
+}
+
+impl TemplatePart {
+    pub fn variables(&self) -> Vec<&str> {
+        match self {
+            TemplatePart::Literal(_) => vec![],
+            TemplatePart::Interpolation(value) => value.variables(),
+        }
+    }
+}
+
+pub type ResourceProperty = (String, Expression);
 
 pub type ResourceDefinition = Vec<ResourceProperty>;
 
-#[derive(Debug, PartialEq)]
+/// A block nested in the body of another one, e.g. `required_providers { ... }` or `lifecycle { ... }`
+#[derive(Debug, Clone, PartialEq)]
+pub struct NestedBlock {
+    pub kind: String,
+    pub labels: Vec<String>,
+    pub body: Body,
+}
+
+/// The contents of a block between braces
+#[derive(Debug, Clone, PartialEq)]
+pub struct Body {
+    pub attributes: ResourceDefinition,
+    pub blocks: Vec<NestedBlock>,
+    /// Comments right before the block, without their delimiters
+    pub comments: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Resource {
     pub res_type: String,
     pub res_def: ResourceDefinition,
     pub res_name: String,
+    pub res_blocks: Vec<NestedBlock>,
+    /// Comments right before the resource, without their delimiters
+    pub comments: Vec<String>,
+    /// Set on the instances of a resource with `count` or `for_each`
+    pub key: Option<InstanceKey>,
+    /// The address of the module instance declaring the resource, e.g. `module.age_field[0]`
+    pub module: Option<String>,
+}
+
+/// Tells the instances of an expanded block apart, e.g. `[0]` or `["hello"]`
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstanceKey {
+    Index(usize),
+    Key(String),
+}
+
+impl fmt::Display for InstanceKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InstanceKey::Index(index) => write!(f, "[{}]", index),
+            InstanceKey::Key(key) => write!(f, "[{:?}]", key),
+        }
+    }
+}
+
+/// A `module "name" { ... }` call
+#[derive(Debug, Clone, PartialEq)]
+pub struct Module {
+    pub name: String,
+    pub body: Body,
+    /// Set on the instances of a module with `count` or `for_each`
+    pub key: Option<InstanceKey>,
+}
+
+impl Module {
+    /// `module.name`, followed by the key of the instance if any
+    pub fn address(&self) -> String {
+        match &self.key {
+            Some(key) => format!("module.{}{}", self.name, key),
+            None => format!("module.{}", self.name),
+        }
+    }
+}
+
+impl Resource {
+    /// `type.name`, followed by the key of the instance if any, and preceded by the module if any
+    pub fn address(&self) -> String {
+        let module = match &self.module {
+            Some(module) => format!("{}.", module),
+            None => String::new(),
+        };
+        match &self.key {
+            Some(key) => format!("{}{}.{}{}", module, self.res_type, self.res_name, key),
+            None => format!("{}{}.{}", module, self.res_type, self.res_name),
+        }
+    }
+
+    /// Returns the value of the property `key`, if the resource defines it
+    pub fn get(&self, key: &str) -> Option<&Expression> {
+        self.res_def.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+}
+
+/// A top level block of a Terraform file
+#[derive(Debug, Clone, PartialEq)]
+pub enum Block {
+    Terraform(Body),
+    Provider(String, Body),
+    Variable(String, Body),
+    Locals(Body),
+    Output(String, Body),
+    Module(Module),
+    Moved(Body),
+    Import(Body),
+    Removed(Body),
+    Check(String, Body),
+    Resource(Resource),
+    Data(Resource),
 }
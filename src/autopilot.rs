@@ -0,0 +1,831 @@
+use crate::{
+    actions::{self, Action, ActionError},
+    ast::{Expression, Resource, TemplatePart, TraversalStep},
+    error::ParserError,
+    json_utils::{self, JsonValue},
+    moved,
+    references::{self, ResourceGraph},
+};
+use std::fmt;
+
+pub const ASSISTANT: &str = "twilio_autopilot_assistants_v1";
+pub const TASK: &str = "twilio_autopilot_assistants_tasks_v1";
+pub const TASK_SAMPLE: &str = "twilio_autopilot_assistants_tasks_samples_v1";
+pub const FIELD_TYPE: &str = "twilio_autopilot_assistants_field_types_v1";
+pub const FIELD_VALUE: &str = "twilio_autopilot_assistants_field_types_field_values_v1";
+
+/// Text shown or said to the user, which may interpolate Terraform expressions
+#[derive(Debug, Clone, PartialEq)]
+pub enum Text {
+    Literal(String),
+    Template(Vec<TemplatePart>),
+}
+
+impl Text {
+    pub fn from_expression(value: &Expression) -> Option<Self> {
+        match value {
+            Expression::String(s) => Some(Text::Literal(s.clone())),
+            Expression::Template(parts) => Some(Text::Template(parts.clone())),
+            _ => None,
+        }
+    }
+
+    pub fn from_json(value: &JsonValue) -> Option<Self> {
+        match value {
+            JsonValue::Str(s) => Some(Text::Literal(s.clone())),
+            JsonValue::Template(parts) => Some(Text::Template(parts.clone())),
+            _ => None,
+        }
+    }
+
+    /// The names of the variables interpolated in the text
+    pub fn variables(&self) -> Vec<&str> {
+        match self {
+            Text::Literal(_) => vec![],
+            Text::Template(parts) => parts.iter().flat_map(TemplatePart::variables).collect(),
+        }
+    }
+}
+
+/// `twilio_autopilot_assistants_v1`, which becomes a Lex bot
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assistant {
+    /// The name of the Terraform resource, see `name_of`
+    pub name: String,
+    pub address: String,
+    pub unique_name: String,
+    pub friendly_name: Option<Text>,
+    pub style_sheet: StyleSheet,
+    pub defaults: Defaults,
+    pub log_queries: bool,
+    pub comments: Vec<String>,
+}
+
+/// The parts of the assistant's `style_sheet` that have a Lex equivalent
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StyleSheet {
+    /// The Amazon Polly voice, without the `Polly.` prefix
+    pub voice: Option<String>,
+}
+
+/// The tasks the assistant falls back to, as `task://unique_name` URIs
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Defaults {
+    pub assistant_initiation: Option<String>,
+    pub fallback: Option<String>,
+    pub collect_validate_on_failure: Option<String>,
+}
+
+/// `twilio_autopilot_assistants_tasks_v1`, which becomes a Lex intent
+#[derive(Debug, Clone, PartialEq)]
+pub struct Task {
+    pub name: String,
+    pub address: String,
+    /// The name of the Terraform resource of the assistant
+    pub assistant: String,
+    pub unique_name: String,
+    pub actions: Vec<Action>,
+    pub comments: Vec<String>,
+}
+
+/// `twilio_autopilot_assistants_tasks_samples_v1`, the utterances of a task
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskSample {
+    pub name: String,
+    pub address: String,
+    pub assistant: String,
+    /// The name of the Terraform resource of the task
+    pub task: String,
+    pub language: Option<String>,
+    pub tagged_text: String,
+}
+
+/// `twilio_autopilot_assistants_field_types_v1`, which becomes a Lex slot type
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldType {
+    pub name: String,
+    pub address: String,
+    pub assistant: String,
+    pub unique_name: String,
+    pub comments: Vec<String>,
+}
+
+/// `twilio_autopilot_assistants_field_types_field_values_v1`, the values of a field type
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldValue {
+    pub name: String,
+    pub address: String,
+    pub assistant: String,
+    /// The name of the Terraform resource of the field type
+    pub field_type: String,
+    pub language: Option<String>,
+    pub value: String,
+    /// The value these ones are synonyms of
+    pub synonym_of: Option<String>,
+}
+
+/// Every Autopilot resource of a configuration, in declaration order.
+/// Resources with `for_each` are expected to be expanded, each instance giving a sample or value.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Model {
+    pub assistants: Vec<Assistant>,
+    pub tasks: Vec<Task>,
+    pub task_samples: Vec<TaskSample>,
+    pub field_types: Vec<FieldType>,
+    pub field_values: Vec<FieldValue>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ModelError {
+    MissingProperty {
+        resource: String,
+        property: &'static str,
+    },
+    MistypedProperty {
+        resource: String,
+        property: &'static str,
+        expected: &'static str,
+    },
+    InvalidActions {
+        resource: String,
+        error: ActionError,
+    },
+    /// Resources that would give Lex resources of the same name
+    NameCollision {
+        first: String,
+        second: String,
+        name: String,
+    },
+    /// A field value whose `synonym_of` isn't a value of its field type
+    UnknownSynonym {
+        field_value: String,
+        synonym_of: String,
+        field_type: String,
+    },
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelError::MissingProperty { resource, property } => {
+                write!(f, "{} is missing the property \"{}\"", resource, property)
+            }
+            ModelError::MistypedProperty {
+                resource,
+                property,
+                expected,
+            } => write!(
+                f,
+                "the property \"{}\" of {} should be {}",
+                property, resource, expected
+            ),
+            ModelError::InvalidActions { resource, error } => {
+                write!(f, "invalid actions in {}: {}", resource, error)
+            }
+            ModelError::NameCollision {
+                first,
+                second,
+                name,
+            } => write!(
+                f,
+                "{} and {} would both give Lex resources named {}",
+                first, second, name
+            ),
+            ModelError::UnknownSynonym {
+                field_value,
+                synonym_of,
+                field_type,
+            } => write!(
+                f,
+                "{} is a synonym of \"{}\", which is not a value of {}",
+                field_value, synonym_of, field_type
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ModelError {}
+
+impl Model {
+    /// Builds the model out of the Autopilot resources, ignoring any other resource. The owners
+    /// of the resources are found like Terraform would, e.g. in the module of the resource first.
+    pub fn from_resources(resources: &[Resource]) -> Result<Self, ModelError> {
+        let graph = ResourceGraph::resolve(resources);
+        let mut model = Model::default();
+
+        for resource in resources {
+            let properties = Properties(resource, &graph);
+            let name = || name_of(&resource.address()).unwrap_or_default();
+            match resource.res_type.as_str() {
+                ASSISTANT => model.assistants.push(Assistant {
+                    name: name(),
+                    address: resource.address(),
+                    unique_name: properties.string("unique_name")?,
+                    friendly_name: properties.optional_text("friendly_name")?,
+                    style_sheet: properties.style_sheet()?,
+                    defaults: properties.defaults()?,
+                    log_queries: properties.optional_bool("log_queries")?.unwrap_or(false),
+                    comments: resource.comments.clone(),
+                }),
+                TASK => model.tasks.push(Task {
+                    name: name(),
+                    address: resource.address(),
+                    assistant: properties.reference("assistant_sid", ASSISTANT)?,
+                    unique_name: properties.string("unique_name")?,
+                    actions: properties.actions()?,
+                    comments: resource.comments.clone(),
+                }),
+                TASK_SAMPLE => model.task_samples.push(TaskSample {
+                    name: name(),
+                    address: resource.address(),
+                    assistant: properties.reference("assistant_sid", ASSISTANT)?,
+                    task: properties.reference("task_sid", TASK)?,
+                    language: properties.optional_string("language")?,
+                    tagged_text: properties.string("tagged_text")?,
+                }),
+                FIELD_TYPE => model.field_types.push(FieldType {
+                    name: name(),
+                    address: resource.address(),
+                    assistant: properties.reference("assistant_sid", ASSISTANT)?,
+                    unique_name: properties.string("unique_name")?,
+                    comments: resource.comments.clone(),
+                }),
+                FIELD_VALUE => model.field_values.push(FieldValue {
+                    name: name(),
+                    address: resource.address(),
+                    assistant: properties.reference("assistant_sid", ASSISTANT)?,
+                    field_type: properties.reference("field_type_sid", FIELD_TYPE)?,
+                    language: properties.optional_string("language")?,
+                    value: properties.string("value")?,
+                    synonym_of: properties.optional_string("synonym_of")?,
+                }),
+                _ => {}
+            }
+        }
+
+        model.check_names()?;
+        model.check_synonyms()?;
+        Ok(model)
+    }
+
+    /// Checks the assistants, tasks and field types don't share the name of their Lex resources,
+    /// which `name_of` may give resources of different modules or keys
+    pub fn check_names(&self) -> Result<(), ModelError> {
+        let names = [
+            self.assistants
+                .iter()
+                .map(|a| (&a.name, &a.address))
+                .collect::<Vec<_>>(),
+            self.tasks.iter().map(|t| (&t.name, &t.address)).collect(),
+            self.field_types
+                .iter()
+                .map(|f| (&f.name, &f.address))
+                .collect(),
+        ];
+        for names in names {
+            for (i, (name, address)) in names.iter().enumerate() {
+                if let Some((_, first)) = names[..i].iter().find(|(n, _)| n == name) {
+                    return Err(ModelError::NameCollision {
+                        first: first.to_string(),
+                        second: address.to_string(),
+                        name: name.to_string(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks every synonym is one of a value of its field type, e.g. `"male"` of `"Boy"`
+    pub fn check_synonyms(&self) -> Result<(), ModelError> {
+        for field_value in &self.field_values {
+            let synonym_of = match &field_value.synonym_of {
+                Some(synonym_of) => synonym_of,
+                None => continue,
+            };
+            let exists = self.field_values.iter().any(|v| {
+                v.field_type == field_value.field_type
+                    && v.synonym_of.is_none()
+                    && v.value == *synonym_of
+            });
+            if !exists {
+                return Err(ModelError::UnknownSynonym {
+                    field_value: field_value.address.clone(),
+                    synonym_of: synonym_of.clone(),
+                    field_type: self
+                        .field_types
+                        .iter()
+                        .find(|f| f.name == field_value.field_type)
+                        .map(|f| f.address.clone())
+                        .unwrap_or_else(|| format!("{}.{}", FIELD_TYPE, field_value.field_type)),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The name of the model of the resource at `address`, which its Lex resources are named after:
+/// its name, preceded by the modules declaring it and followed by its instance key, e.g.
+/// `age_field_0_age` for `module.age_field[0].twilio_autopilot_assistants_field_types_v1.age`
+pub fn name_of(address: &str) -> Option<String> {
+    let (modules, _, res_name) = moved::resource_of(address)?;
+
+    // Terraform names are made of letters, digits, underscores and dashes
+    let mut name = String::new();
+    for part in modules.into_iter().chain([res_name]) {
+        let mut separate = !name.is_empty();
+        for c in part.chars() {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                if separate {
+                    name.push('_');
+                    separate = false;
+                }
+                name.push(c);
+            } else {
+                separate = !name.is_empty();
+            }
+        }
+    }
+    Some(name)
+}
+
+/// Typed access to the properties of a resource, failing with the address of the resource
+struct Properties<'a>(&'a Resource, &'a ResourceGraph<'a>);
+
+impl<'a> Properties<'a> {
+    fn missing(&self, property: &'static str) -> ModelError {
+        ModelError::MissingProperty {
+            resource: self.0.address(),
+            property,
+        }
+    }
+
+    fn mistyped(&self, property: &'static str, expected: &'static str) -> ModelError {
+        ModelError::MistypedProperty {
+            resource: self.0.address(),
+            property,
+            expected,
+        }
+    }
+
+    fn optional_string(&self, property: &'static str) -> Result<Option<String>, ModelError> {
+        match self.0.get(property) {
+            None | Some(Expression::Null) => Ok(None),
+            Some(Expression::String(s)) => Ok(Some(s.clone())),
+            Some(_) => Err(self.mistyped(property, "a string")),
+        }
+    }
+
+    fn string(&self, property: &'static str) -> Result<String, ModelError> {
+        self.optional_string(property)?
+            .ok_or_else(|| self.missing(property))
+    }
+
+    fn optional_text(&self, property: &'static str) -> Result<Option<Text>, ModelError> {
+        match self.0.get(property) {
+            None | Some(Expression::Null) => Ok(None),
+            Some(value) => Text::from_expression(value)
+                .map(Some)
+                .ok_or_else(|| self.mistyped(property, "a string")),
+        }
+    }
+
+    fn optional_bool(&self, property: &'static str) -> Result<Option<bool>, ModelError> {
+        match self.0.get(property) {
+            None | Some(Expression::Null) => Ok(None),
+            Some(Expression::Boolean(b)) => Ok(Some(*b)),
+            Some(_) => Err(self.mistyped(property, "a boolean")),
+        }
+    }
+
+    fn optional_json(&self, property: &'static str) -> Result<Option<JsonValue>, ModelError> {
+        match self.0.get(property) {
+            None | Some(Expression::Null) => Ok(None),
+            Some(Expression::Json(json)) => Ok(Some(json.clone())),
+            // A document already encoded, e.g. by the script generating a `.tf.json` file
+            Some(Expression::String(document)) => {
+                match json_utils::root::<ParserError<&str>>(document) {
+                    Ok(("", json)) => Ok(Some(json)),
+                    _ => Err(self.mistyped(property, "a JSON document")),
+                }
+            }
+            Some(_) => Err(self.mistyped(property, "a jsonencode({...}) document")),
+        }
+    }
+
+    /// The name of the resource of type `res_type` referenced by `property`, see `name_of`,
+    /// e.g. `survey` for `twilio_autopilot_assistants_v1.survey.sid`, or `survey_hi` for an
+    /// instance like `twilio_autopilot_assistants_v1.survey["hi"].sid`
+    fn reference(&self, property: &'static str, res_type: &str) -> Result<String, ModelError> {
+        let value = self.0.get(property).ok_or_else(|| self.missing(property))?;
+        let (res_name, key) = match value {
+            Expression::Traversal(root, steps) => match (root.as_ref(), steps.as_slice()) {
+                (
+                    Expression::Identifier(referenced_type),
+                    [TraversalStep::GetAttr(res_name), attr @ ..],
+                ) if referenced_type == res_type => (res_name, references::instance_key(attr)),
+                _ => {
+                    return Err(self.mistyped(property, "a reference to another Autopilot resource"))
+                }
+            },
+            _ => return Err(self.mistyped(property, "a reference to another Autopilot resource")),
+        };
+
+        let target = self.1.references.iter().find(|r| {
+            std::ptr::eq(r.from, self.0) && r.property == property && r.target.res_type == res_type
+        });
+        // A resource that isn't defined gets the name it would have next to this one
+        let address = match target {
+            Some(reference) => reference.target.address(),
+            None => {
+                let module = match &self.0.module {
+                    Some(module) => format!("{}.", module),
+                    None => String::new(),
+                };
+                let key = key.as_ref().map(ToString::to_string).unwrap_or_default();
+                format!("{}{}.{}{}", module, res_type, res_name, key)
+            }
+        };
+        Ok(name_of(&address).unwrap_or_default())
+    }
+
+    fn actions(&self) -> Result<Vec<Action>, ModelError> {
+        match self.optional_json("actions")? {
+            Some(json) => {
+                actions::parse_actions(&json).map_err(|error| ModelError::InvalidActions {
+                    resource: self.0.address(),
+                    error,
+                })
+            }
+            None => Ok(vec![]),
+        }
+    }
+
+    fn style_sheet(&self) -> Result<StyleSheet, ModelError> {
+        match self.optional_json("style_sheet")? {
+            Some(json) => StyleSheet::from_json(&json).ok_or_else(|| {
+                self.mistyped("style_sheet", "a style sheet with a string say_voice")
+            }),
+            None => Ok(StyleSheet::default()),
+        }
+    }
+
+    fn defaults(&self) -> Result<Defaults, ModelError> {
+        match self.optional_json("defaults")? {
+            Some(json) => Defaults::from_json(&json)
+                .ok_or_else(|| self.mistyped("defaults", "defaults made of task URIs")),
+            None => Ok(Defaults::default()),
+        }
+    }
+}
+
+impl StyleSheet {
+    /// Reads a `{ "style_sheet": {...} }` document, `None` meaning it is mistyped
+    pub fn from_json(style_sheet: &JsonValue) -> Option<Self> {
+        let voice = style_sheet
+            .get("style_sheet")
+            .and_then(|s| s.get("voice"))
+            .and_then(|v| v.get("say_voice"));
+
+        match voice {
+            None => Some(StyleSheet::default()),
+            Some(voice) => Some(StyleSheet {
+                voice: Some(voice.as_str()?.trim_start_matches("Polly.").to_string()),
+            }),
+        }
+    }
+}
+
+impl Defaults {
+    /// Reads a `{ "defaults": {...} }` document, `None` meaning it is mistyped
+    pub fn from_json(defaults: &JsonValue) -> Option<Self> {
+        let defaults = defaults.get("defaults");
+
+        let task = |value: Option<&JsonValue>| match value {
+            None => Some(None),
+            Some(value) => value.as_str().map(|s| Some(s.to_string())),
+        };
+
+        Some(Defaults {
+            assistant_initiation: task(defaults.and_then(|d| d.get("assistant_initiation")))?,
+            fallback: task(defaults.and_then(|d| d.get("fallback")))?,
+            collect_validate_on_failure: task(
+                defaults
+                    .and_then(|d| d.get("collect"))
+                    .and_then(|c| c.get("validate_on_failure")),
+            )?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        expand::expand,
+        parser::{only_resources, parse_blocks, Mode},
+        tf_json,
+    };
+    use std::collections::HashMap;
+
+    fn resources_from(source: &str) -> Vec<Resource> {
+        let (_, blocks) = parse_blocks(source).expect("Failed parsing the data");
+        let blocks = expand(blocks, &HashMap::new()).expect("Failed expanding the blocks");
+        only_resources(blocks)
+    }
+
+    fn model_from(source: &str) -> Result<Model, ModelError> {
+        Model::from_resources(&resources_from(source))
+    }
+
+    /// The resources of `source`, declared in the root and in `module.age_field[0]`
+    fn resources_in_modules(source: &str) -> Vec<Resource> {
+        let root = resources_from(source);
+        let mut module = root.clone();
+        for resource in &mut module {
+            resource.module = Some("module.age_field[0]".to_string());
+        }
+        root.into_iter().chain(module).collect()
+    }
+
+    #[test]
+    fn build_the_development_pre_survey() {
+        let source = std::fs::read_to_string("./development_pre_survey.tf").unwrap();
+        let model = model_from(&source).unwrap();
+
+        let assistant = &model.assistants[0];
+        assert_eq!(assistant.unique_name, "demo_chatbot");
+        assert_eq!(assistant.style_sheet.voice.as_deref(), Some("Matthew"));
+        assert_eq!(
+            assistant.defaults.fallback.as_deref(),
+            Some("task://fallback")
+        );
+        assert!(assistant.log_queries);
+
+        assert_eq!(model.tasks.len(), 9);
+        // 16 greetings, and 16 goodbyes once "no thanks" is deduplicated
+        assert_eq!(model.task_samples.len(), 32);
+        assert_eq!(model.field_types.len(), 2);
+        assert_eq!(model.field_values.len(), 132);
+
+        let synonyms: Vec<&FieldValue> = model
+            .field_values
+            .iter()
+            .filter(|v| v.synonym_of.as_deref() == Some("Boy"))
+            .collect();
+        assert_eq!(synonyms.len(), 7);
+        assert_eq!(synonyms[0].field_type, "development_pre_survey_Gender");
+        assert_eq!(synonyms[0].value, "B");
+    }
+
+    #[test]
+    fn read_encoded_actions() {
+        let task = |actions: &str| {
+            format!(
+                "resource \"twilio_autopilot_assistants_tasks_v1\" \"greeting\" {{
+                    unique_name   = \"greeting\"
+                    assistant_sid = twilio_autopilot_assistants_v1.survey.sid
+                    actions       = {}
+                }}",
+                actions
+            )
+        };
+        let expected = model_from(&task(r#"jsonencode({ "actions" : [{ "say" : "Hi!" }] })"#))
+            .unwrap()
+            .tasks;
+
+        let heredoc = task("<<EOT\n{ \"actions\" : [{ \"say\" : \"Hi!\" }] }\nEOT");
+        assert_eq!(model_from(&heredoc).unwrap().tasks, expected);
+
+        let json = r#"{ "resource": { "twilio_autopilot_assistants_tasks_v1": { "greeting": {
+            "unique_name": "greeting",
+            "assistant_sid": "${twilio_autopilot_assistants_v1.survey.sid}",
+            "actions": "{\"actions\": [{\"say\": \"Hi!\"}]}"
+        } } } }"#;
+        let parsed = tf_json::parse_file("main.tf.json", json, Mode::Strict).unwrap();
+        let model = Model::from_resources(&only_resources(parsed.blocks)).unwrap();
+        assert_eq!(model.tasks, expected);
+
+        let error = model_from(&task("\"not json\"")).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the property \"actions\" of twilio_autopilot_assistants_tasks_v1.greeting should be a JSON document"
+        );
+    }
+
+    #[test]
+    fn report_missing_properties() {
+        let error = model_from(
+            "resource \"twilio_autopilot_assistants_tasks_v1\" \"greeting\" {
+                assistant_sid = twilio_autopilot_assistants_v1.survey.sid
+            }",
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "twilio_autopilot_assistants_tasks_v1.greeting is missing the property \"unique_name\""
+        );
+    }
+
+    #[test]
+    fn report_unknown_synonyms() {
+        let error = model_from(
+            "resource \"twilio_autopilot_assistants_field_types_field_values_v1\" \"gender_group\" {
+                for_each       = toset([\"Boy\", \"Girl\"])
+                assistant_sid  = twilio_autopilot_assistants_v1.survey.sid
+                field_type_sid = twilio_autopilot_assistants_field_types_v1.gender.sid
+                value          = each.key
+            }
+            resource \"twilio_autopilot_assistants_field_types_field_values_v1\" \"nb_group\" {
+                for_each       = toset([\"nb\"])
+                assistant_sid  = twilio_autopilot_assistants_v1.survey.sid
+                field_type_sid = twilio_autopilot_assistants_field_types_v1.gender.sid
+                synonym_of     = \"Non-Binary\"
+                value          = each.key
+            }",
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "twilio_autopilot_assistants_field_types_field_values_v1.nb_group[\"nb\"] is a synonym of \"Non-Binary\", which is not a value of twilio_autopilot_assistants_field_types_v1.gender"
+        );
+    }
+
+    #[test]
+    fn name_resources_after_their_modules() {
+        assert_eq!(
+            name_of("module.age_field[0].twilio_autopilot_assistants_field_types_v1.age"),
+            Some("age_field_0_age".to_string())
+        );
+        assert_eq!(
+            name_of("module.a[\"x y\"].module.b.twilio_autopilot_assistants_tasks_v1.hi[\"?\"]"),
+            Some("a_x_y_b_hi".to_string())
+        );
+        assert_eq!(
+            name_of("twilio_autopilot_assistants_v1.survey"),
+            Some("survey".to_string())
+        );
+        assert_eq!(name_of("module.age_field"), None);
+    }
+
+    #[test]
+    fn own_resources_in_their_modules() {
+        let resources = resources_in_modules(
+            "resource \"twilio_autopilot_assistants_v1\" \"survey\" {
+                unique_name = \"survey\"
+            }
+            resource \"twilio_autopilot_assistants_field_types_v1\" \"age\" {
+                assistant_sid = twilio_autopilot_assistants_v1.survey.sid
+                unique_name   = \"Age\"
+            }
+            resource \"twilio_autopilot_assistants_field_types_field_values_v1\" \"unknown_age\" {
+                for_each       = toset([\"idk\"])
+                assistant_sid  = twilio_autopilot_assistants_v1.survey.sid
+                field_type_sid = twilio_autopilot_assistants_field_types_v1.age.sid
+                value          = each.key
+            }",
+        );
+        let model = Model::from_resources(&resources).unwrap();
+
+        let names = |names: Vec<(&String, &String)>| {
+            names
+                .into_iter()
+                .map(|(a, b)| format!("{} {}", a, b))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names(
+                model
+                    .field_types
+                    .iter()
+                    .map(|f| (&f.name, &f.assistant))
+                    .collect()
+            ),
+            vec!["age survey", "age_field_0_age age_field_0_survey"]
+        );
+        assert_eq!(
+            names(
+                model
+                    .field_values
+                    .iter()
+                    .map(|v| (&v.name, &v.field_type))
+                    .collect()
+            ),
+            vec![
+                "unknown_age_idk age",
+                "age_field_0_unknown_age_idk age_field_0_age"
+            ]
+        );
+    }
+
+    #[test]
+    fn own_resources_by_instance() {
+        let mut resources = resources_from(
+            "resource \"twilio_autopilot_assistants_v1\" \"survey\" {
+                for_each    = toset([\"hi\", \"bye\"])
+                unique_name = each.key
+            }
+            resource \"twilio_autopilot_assistants_tasks_v1\" \"greeting\" {
+                assistant_sid = twilio_autopilot_assistants_v1.survey[\"bye\"].sid
+                unique_name   = \"greeting\"
+            }
+            resource \"twilio_autopilot_assistants_tasks_v1\" \"goodbye\" {
+                assistant_sid = twilio_autopilot_assistants_v1.survey[\"nope\"].sid
+                unique_name   = \"goodbye\"
+            }",
+        );
+        let mut indexed = resources[2].clone();
+        indexed.res_name = "goodnight".to_string();
+        indexed.res_def[0].1 = Expression::Traversal(
+            Box::new(Expression::Identifier(ASSISTANT.to_string())),
+            vec![
+                TraversalStep::GetAttr("survey".to_string()),
+                TraversalStep::Index(Expression::Number(0.0)),
+                TraversalStep::GetAttr("sid".to_string()),
+            ],
+        );
+        resources.push(indexed);
+        let model = Model::from_resources(&resources).unwrap();
+
+        assert_eq!(
+            model
+                .tasks
+                .iter()
+                .map(|t| format!("{} {}", t.name, t.assistant))
+                .collect::<Vec<_>>(),
+            vec![
+                "greeting survey_bye",
+                "goodbye survey_nope",
+                "goodnight survey_0"
+            ]
+        );
+    }
+
+    #[test]
+    fn report_name_collisions() {
+        let resources = resources_in_modules(
+            "resource \"twilio_autopilot_assistants_field_types_v1\" \"age\" {
+                assistant_sid = twilio_autopilot_assistants_v1.survey.sid
+                unique_name   = \"Age\"
+            }",
+        );
+        let mut renamed = resources[0].clone();
+        renamed.res_name = "age_field_0_age".to_string();
+        let error = Model::from_resources(&[renamed, resources[1].clone()]).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "twilio_autopilot_assistants_field_types_v1.age_field_0_age and \
+            module.age_field[0].twilio_autopilot_assistants_field_types_v1.age would both give Lex \
+            resources named age_field_0_age"
+        );
+    }
+
+    #[test]
+    fn report_mistyped_properties() {
+        let error = model_from(
+            "resource \"twilio_autopilot_assistants_v1\" \"survey\" {
+                unique_name = \"survey\"
+                log_queries = \"yes\"
+            }",
+        )
+        .unwrap_err();
+        assert_eq!(
+            error,
+            ModelError::MistypedProperty {
+                resource: "twilio_autopilot_assistants_v1.survey".to_string(),
+                property: "log_queries",
+                expected: "a boolean",
+            }
+        );
+
+        let error = model_from(
+            "resource \"twilio_autopilot_assistants_tasks_v1\" \"greeting\" {
+                unique_name   = \"greeting\"
+                assistant_sid = \"UA123\"
+            }",
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the property \"assistant_sid\" of twilio_autopilot_assistants_tasks_v1.greeting should be a reference to another Autopilot resource"
+        );
+
+        let resources = resources_in_modules(
+            "resource \"twilio_autopilot_assistants_field_types_field_values_v1\" \"unknown_age\" {
+                for_each       = toset([\"idk\"])
+                assistant_sid  = twilio_autopilot_assistants_v1.survey.sid
+                field_type_sid = twilio_autopilot_assistants_field_types_v1.age.sid
+                value          = 42
+            }",
+        );
+        let error = Model::from_resources(&resources[1..]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the property \"value\" of module.age_field[0].twilio_autopilot_assistants_field_types_field_values_v1.unknown_age[\"idk\"] should be a string"
+        );
+    }
+}
@@ -0,0 +1,246 @@
+use nom::{
+    error::{ContextError, ErrorKind, FromExternalError, ParseError},
+    InputLength,
+};
+use std::fmt;
+
+/// The error of the Terraform parsers. Unlike nom's default error it keeps the `context(...)`
+/// stack the failure happened in, and the character that was expected if any, so it can be
+/// turned into a `Diagnostic` pointing at the failing line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParserError<I> {
+    /// The input left when the innermost parser failed
+    pub input: I,
+    pub kind: ErrorKind,
+    pub expected: Option<char>,
+    /// The contexts the error went through, innermost first
+    pub contexts: Vec<(I, &'static str)>,
+}
+
+impl<I> ParseError<I> for ParserError<I>
+where
+    I: InputLength,
+{
+    fn from_error_kind(input: I, kind: ErrorKind) -> Self {
+        ParserError {
+            input,
+            kind,
+            expected: None,
+            contexts: vec![],
+        }
+    }
+
+    fn from_char(input: I, c: char) -> Self {
+        ParserError {
+            expected: Some(c),
+            ..Self::from_error_kind(input, ErrorKind::Char)
+        }
+    }
+
+    /// Keeps the innermost error, it's the one that knows what went wrong
+    fn append(_: I, _: ErrorKind, other: Self) -> Self {
+        other
+    }
+
+    /// When every branch of an `alt` failed, the one that got the furthest is reported
+    fn or(self, other: Self) -> Self {
+        match other.input.input_len() <= self.input.input_len() {
+            true => other,
+            false => self,
+        }
+    }
+}
+
+impl<I> ContextError<I> for ParserError<I> {
+    fn add_context(input: I, context: &'static str, mut other: Self) -> Self {
+        other.contexts.push((input, context));
+        other
+    }
+}
+
+impl<I, E> FromExternalError<I, E> for ParserError<I>
+where
+    I: InputLength,
+{
+    fn from_external_error(input: I, kind: ErrorKind, _: E) -> Self {
+        Self::from_error_kind(input, kind)
+    }
+}
+
+/// A parser error located in its source file, displayed like
+///
+/// ```text
+/// main.tf:4:11: expected ':' (in block > attribute > map)
+///   |
+/// 4 |     "say" "hi"
+///   |           ^
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub file: String,
+    /// 1-based
+    pub line: usize,
+    /// 1-based, in characters
+    pub column: usize,
+    /// The whole line the error is on
+    pub snippet: String,
+    pub message: String,
+    /// Outermost first, e.g. `["block", "attribute", "map"]`
+    pub contexts: Vec<&'static str>,
+}
+
+impl Diagnostic {
+    /// Locates `error` in `source`, the content of `file` it was raised while parsing
+    pub fn new(file: &str, source: &str, error: nom::Err<ParserError<&str>>) -> Self {
+        let error = match error {
+            nom::Err::Error(e) | nom::Err::Failure(e) => e,
+            nom::Err::Incomplete(_) => ParserError::from_error_kind("", ErrorKind::Complete),
+        };
+
+        let message = match error.expected {
+            Some(c) => format!("expected '{}'", c),
+            None => format!("unexpected input ({})", error.kind.description()),
+        };
+        let mut diagnostic = Diagnostic::at(file, source, error.input, message);
+        diagnostic.contexts = error.contexts.iter().rev().map(|(_, c)| *c).collect();
+
+        diagnostic
+    }
+
+    /// A diagnostic pointing at the start of `rest`, what was left of `source` when something
+    /// went wrong
+    pub fn at(file: &str, source: &str, rest: &str, message: String) -> Self {
+        let offset = source.len() - rest.len().min(source.len());
+        let line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[offset..]
+            .find('\n')
+            .map_or(source.len(), |i| offset + i);
+        let Span { line, column, .. } = Span::at(file, source, rest);
+
+        Diagnostic {
+            file: file.to_string(),
+            line,
+            column,
+            snippet: source[line_start..line_end]
+                .trim_end_matches('\r')
+                .to_string(),
+            message,
+            contexts: vec![],
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {}",
+            self.file, self.line, self.column, self.message
+        )?;
+        if !self.contexts.is_empty() {
+            write!(f, " (in {})", self.contexts.join(" > "))?;
+        }
+        writeln!(f)?;
+
+        let gutter = " ".repeat(self.line.to_string().len());
+        // Tabs are kept in the padding so the caret lines up with the snippet
+        let padding: String = self
+            .snippet
+            .chars()
+            .take(self.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", self.line, self.snippet)?;
+        write!(f, "{} | {}^", gutter, padding)
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+/// Where something starts in a source file, displayed like `main.tf:4:11`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub file: String,
+    /// 1-based
+    pub line: usize,
+    /// 1-based, in characters
+    pub column: usize,
+}
+
+impl Span {
+    /// The start of `rest`, what is left of `source` at some point of its parsing
+    pub fn at(file: &str, source: &str, rest: &str) -> Self {
+        let offset = source.len() - rest.len().min(source.len());
+        let before = &source[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+
+        Span {
+            file: file.to_string(),
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_resources;
+
+    #[test]
+    fn locate_errors() {
+        let source = "resource \"res_type\" \"res_name\" {
+  unique_name = \"name\"
+  actions = jsonencode({
+    \"say\" \"hi\"
+  })
+}";
+
+        let error = parse_resources(source).unwrap_err();
+        let diagnostic = Diagnostic::new("main.tf", source, error);
+
+        assert_eq!(diagnostic.line, 4);
+        assert_eq!(diagnostic.column, 11);
+        assert_eq!(diagnostic.snippet, "    \"say\" \"hi\"");
+        assert_eq!(
+            diagnostic.to_string(),
+            "main.tf:4:11: expected ':' (in block > attribute > map)
+  |
+4 |     \"say\" \"hi\"
+  |           ^"
+        );
+    }
+
+    #[test]
+    fn keep_the_json_contexts() {
+        let source = "resource \"res_type\" \"res_name\" {
+  actions = jsonencode({ \"actions\" : [{ \"say\" : \"hi }] })
+}";
+
+        let error = parse_resources(source).unwrap_err();
+        let diagnostic = Diagnostic::new("main.tf", source, error);
+
+        assert!(
+            diagnostic
+                .to_string()
+                .contains("(in block > attribute > map > array > map > string)"),
+            "{}",
+            diagnostic
+        );
+    }
+
+    #[test]
+    fn locate_the_end_of_the_file() {
+        let diagnostic = Diagnostic::at("main.tf", "a\nbc", "", "unexpected end".to_string());
+
+        assert_eq!((diagnostic.line, diagnostic.column), (2, 3));
+        assert_eq!(diagnostic.snippet, "bc");
+    }
+}
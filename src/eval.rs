@@ -0,0 +1,387 @@
+use crate::{
+    ast::{BinaryOperator, Expression, TemplatePart, TraversalStep, UnaryOperator},
+    json_utils::JsonValue,
+    parser::{literal_template, template_expression},
+};
+use std::collections::HashMap;
+
+/// The values known while expanding a block, substituted in its expressions
+#[derive(Debug, Clone, Default)]
+pub struct Scope {
+    /// `each.key` and `each.value`
+    pub each: Option<(Expression, Expression)>,
+    /// `count.index`
+    pub count_index: Option<usize>,
+    /// The values of `var.*`
+    pub variables: HashMap<String, Expression>,
+}
+
+/// Substitutes the known values in `value` and folds whatever they make constant.
+/// Anything else, e.g. references to other resources, is kept as is.
+pub fn evaluate(value: &Expression, scope: &Scope) -> Expression {
+    match value {
+        Expression::Template(parts) => template_expression(evaluate_parts(parts, scope)),
+        Expression::Json(json) => Expression::Json(evaluate_json(json, scope)),
+        Expression::Tuple(items) => {
+            Expression::Tuple(items.iter().map(|i| evaluate(i, scope)).collect())
+        }
+        Expression::Object(items) => Expression::Object(
+            items
+                .iter()
+                .map(|(k, v)| (evaluate(k, scope), evaluate(v, scope)))
+                .collect(),
+        ),
+        Expression::Traversal(root, steps) => {
+            let steps = steps.iter().map(|step| match step {
+                TraversalStep::Index(key) => TraversalStep::Index(evaluate(key, scope)),
+                step => step.clone(),
+            });
+            traverse(evaluate(root, scope), steps.collect(), scope)
+        }
+        Expression::FunctionCall {
+            name,
+            args,
+            expand_final,
+        } => call(
+            name,
+            args.iter().map(|a| evaluate(a, scope)).collect(),
+            *expand_final,
+        ),
+        Expression::Unary(operator, operand) => unary(*operator, evaluate(operand, scope)),
+        Expression::Binary(operator, lhs, rhs) => {
+            binary(*operator, evaluate(lhs, scope), evaluate(rhs, scope))
+        }
+        Expression::Conditional {
+            condition,
+            then,
+            otherwise,
+        } => match evaluate(condition, scope) {
+            Expression::Boolean(true) => evaluate(then, scope),
+            Expression::Boolean(false) => evaluate(otherwise, scope),
+            condition => Expression::Conditional {
+                condition: Box::new(condition),
+                then: Box::new(evaluate(then, scope)),
+                otherwise: Box::new(evaluate(otherwise, scope)),
+            },
+        },
+        Expression::For(f) => {
+            let mut f = f.clone();
+            f.collection = evaluate(&f.collection, scope);
+            f.key = f.key.map(|k| evaluate(&k, scope));
+            f.value = evaluate(&f.value, scope);
+            f.condition = f.condition.map(|c| evaluate(&c, scope));
+            Expression::For(f)
+        }
+        Expression::Parenthesized(inner) => match evaluate(inner, scope) {
+            inner if is_literal(&inner) => inner,
+            inner => Expression::Parenthesized(Box::new(inner)),
+        },
+        Expression::Null
+        | Expression::Boolean(_)
+        | Expression::Number(_)
+        | Expression::String(_)
+        | Expression::Identifier(_) => value.clone(),
+    }
+}
+
+fn is_literal(value: &Expression) -> bool {
+    matches!(
+        value,
+        Expression::Null | Expression::Boolean(_) | Expression::Number(_) | Expression::String(_)
+    )
+}
+
+/// The text of a literal when interpolated in a string
+fn literal_text(value: &Expression) -> Option<String> {
+    match value {
+        Expression::Boolean(b) => Some(b.to_string()),
+        Expression::Number(n) => Some(n.to_string()),
+        Expression::String(s) => Some(s.clone()),
+        _ => None,
+    }
+}
+
+/// Evaluates the interpolations, merging the ones that become literal with the text around them
+fn evaluate_parts(parts: &[TemplatePart], scope: &Scope) -> Vec<TemplatePart> {
+    let mut evaluated: Vec<TemplatePart> = vec![];
+    for part in parts {
+        let part = match part {
+            TemplatePart::Literal(s) => TemplatePart::Literal(s.clone()),
+            TemplatePart::Interpolation(value) => {
+                let value = evaluate(value, scope);
+                match literal_text(&value) {
+                    Some(text) => TemplatePart::Literal(text),
+                    None => TemplatePart::Interpolation(value),
+                }
+            }
+        };
+        match (evaluated.last_mut(), part) {
+            (Some(TemplatePart::Literal(previous)), TemplatePart::Literal(s)) => {
+                previous.push_str(&s)
+            }
+            (_, part) => evaluated.push(part),
+        }
+    }
+    evaluated
+}
+
+fn evaluate_json(json: &JsonValue, scope: &Scope) -> JsonValue {
+    match json {
+        JsonValue::Template(parts) => {
+            let parts = evaluate_parts(parts, scope);
+            match literal_template(&parts) {
+                Some(s) => JsonValue::Str(s),
+                None => JsonValue::Template(parts),
+            }
+        }
+        JsonValue::Array(values) => {
+            JsonValue::Array(values.iter().map(|v| evaluate_json(v, scope)).collect())
+        }
+        JsonValue::Object(object) => JsonValue::Object(
+            object
+                .iter()
+                .map(|(k, v)| (k.clone(), evaluate_json(v, scope)))
+                .collect(),
+        ),
+        _ => json.clone(),
+    }
+}
+
+/// The value of `root.attr` when the scope knows it, e.g. `each.key` or `var.name`
+fn named_value(root: &str, attr: &str, scope: &Scope) -> Option<Expression> {
+    match (root, attr) {
+        ("each", "key") => scope.each.as_ref().map(|(key, _)| key.clone()),
+        ("each", "value") => scope.each.as_ref().map(|(_, value)| value.clone()),
+        ("count", "index") => scope.count_index.map(|i| Expression::Number(i as f64)),
+        ("var", name) => scope.variables.get(name).cloned(),
+        _ => None,
+    }
+}
+
+/// Applies the steps to `root`, substituting the values of the scope and looking up the keys of
+/// literal collections
+fn traverse(root: Expression, steps: Vec<TraversalStep>, scope: &Scope) -> Expression {
+    let mut steps = steps.into_iter().peekable();
+
+    let known = match (&root, steps.peek()) {
+        (Expression::Identifier(name), Some(TraversalStep::GetAttr(attr))) => {
+            named_value(name, attr, scope)
+        }
+        _ => None,
+    };
+    let mut value = match known {
+        Some(value) => {
+            steps.next();
+            value
+        }
+        None => root,
+    };
+
+    while let Some(step) = steps.peek() {
+        match lookup(&value, step) {
+            Some(item) => {
+                value = item.clone();
+                steps.next();
+            }
+            None => break,
+        }
+    }
+
+    let steps: Vec<TraversalStep> = steps.collect();
+    match (value, steps.is_empty()) {
+        (value, true) => value,
+        (Expression::Traversal(root, mut first), false) => {
+            first.extend(steps);
+            Expression::Traversal(root, first)
+        }
+        (value, false) => Expression::Traversal(Box::new(value), steps),
+    }
+}
+
+fn lookup<'a>(collection: &'a Expression, step: &TraversalStep) -> Option<&'a Expression> {
+    match (collection, step) {
+        (Expression::Object(items), TraversalStep::GetAttr(key))
+        | (Expression::Object(items), TraversalStep::Index(Expression::String(key))) => items
+            .iter()
+            .find(|(k, _)| matches!(k, Expression::String(k) if k == key))
+            .map(|(_, v)| v),
+        (Expression::Tuple(items), TraversalStep::Index(Expression::Number(index)))
+            if index.fract() == 0.0 && *index >= 0.0 =>
+        {
+            items.get(*index as usize)
+        }
+        _ => None,
+    }
+}
+
+fn unary(operator: UnaryOperator, operand: Expression) -> Expression {
+    match (operator, &operand) {
+        (UnaryOperator::Not, Expression::Boolean(b)) => Expression::Boolean(!b),
+        (UnaryOperator::Negate, Expression::Number(n)) => Expression::Number(-n),
+        _ => Expression::Unary(operator, Box::new(operand)),
+    }
+}
+
+fn binary(operator: BinaryOperator, lhs: Expression, rhs: Expression) -> Expression {
+    use BinaryOperator::*;
+
+    match (operator, &lhs, &rhs) {
+        (Equal, lhs, rhs) if is_literal(lhs) && is_literal(rhs) => Expression::Boolean(lhs == rhs),
+        (NotEqual, lhs, rhs) if is_literal(lhs) && is_literal(rhs) => {
+            Expression::Boolean(lhs != rhs)
+        }
+        (And, Expression::Boolean(a), Expression::Boolean(b)) => Expression::Boolean(*a && *b),
+        (Or, Expression::Boolean(a), Expression::Boolean(b)) => Expression::Boolean(*a || *b),
+        (operator, Expression::Number(a), Expression::Number(b)) => match operator {
+            Less => Expression::Boolean(a < b),
+            LessOrEqual => Expression::Boolean(a <= b),
+            Greater => Expression::Boolean(a > b),
+            GreaterOrEqual => Expression::Boolean(a >= b),
+            Add => Expression::Number(a + b),
+            Subtract => Expression::Number(a - b),
+            Multiply => Expression::Number(a * b),
+            Divide if *b != 0.0 => Expression::Number(a / b),
+            Modulo if *b != 0.0 => Expression::Number(a % b),
+            _ => Expression::Binary(operator, Box::new(lhs), Box::new(rhs)),
+        },
+        _ => Expression::Binary(operator, Box::new(lhs), Box::new(rhs)),
+    }
+}
+
+/// Folds the type conversion functions when their argument is known
+fn call(name: &str, args: Vec<Expression>, expand_final: bool) -> Expression {
+    match (name, args.as_slice(), expand_final) {
+        ("toset", [Expression::Tuple(items)], false) if items.iter().all(is_literal) => {
+            let mut set: Vec<Expression> = vec![];
+            for item in items {
+                if !set.contains(item) {
+                    set.push(item.clone());
+                }
+            }
+            Expression::FunctionCall {
+                name: name.to_string(),
+                args: vec![Expression::Tuple(set)],
+                expand_final,
+            }
+        }
+        ("tolist", [tuple @ Expression::Tuple(_)], false)
+        | ("tomap", [tuple @ Expression::Object(_)], false) => tuple.clone(),
+        _ => Expression::FunctionCall {
+            name: name.to_string(),
+            args,
+            expand_final,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_expression;
+
+    fn evaluate_source(source: &str, scope: &Scope) -> Expression {
+        let (rest, value) = parse_expression(source).unwrap();
+        assert_eq!(rest, "");
+        evaluate(&value, scope)
+    }
+
+    fn each(key: &str, value: Expression) -> Scope {
+        Scope {
+            each: Some((Expression::String(key.to_string()), value)),
+            ..Scope::default()
+        }
+    }
+
+    #[test]
+    fn substitute_each() {
+        let scope = each(
+            "boy",
+            Expression::Object(vec![(
+                Expression::String("synonyms".to_string()),
+                Expression::Tuple(vec![Expression::String("male".to_string())]),
+            )]),
+        );
+
+        assert_eq!(
+            evaluate_source("each.key", &scope),
+            Expression::String("boy".to_string())
+        );
+        assert_eq!(
+            evaluate_source("\"${each.key} is ${each.value.synonyms[0]}\"", &scope),
+            Expression::String("boy is male".to_string())
+        );
+        assert_eq!(
+            evaluate_source("each.value.synonyms[1]", &scope),
+            Expression::Traversal(
+                Box::new(Expression::Tuple(vec![Expression::String(
+                    "male".to_string()
+                )])),
+                vec![TraversalStep::Index(Expression::Number(1.0))]
+            )
+        );
+        assert_eq!(
+            evaluate_source("jsonencode({ \"say\" : \"Hi ${each.key}\" })", &scope),
+            evaluate_source("jsonencode({ \"say\" : \"Hi boy\" })", &Scope::default())
+        );
+    }
+
+    #[test]
+    fn keep_unknown_values() {
+        let scope = each("boy", Expression::Null);
+
+        for source in [
+            "twilio_autopilot_assistants_v1.survey.sid",
+            "\"Hi ${var.name}\"",
+            "[for k, v in var.tags : v if k != \"\"]",
+        ] {
+            let (_, value) = parse_expression(source).unwrap();
+            assert_eq!(evaluate(&value, &scope), value);
+        }
+    }
+
+    #[test]
+    fn fold_sets() {
+        assert_eq!(
+            evaluate_source("toset([\"no\", \"bye\", \"no\"])", &Scope::default()),
+            evaluate_source("toset([\"no\", \"bye\"])", &Scope::default())
+        );
+        assert_eq!(
+            evaluate_source("tomap({ a = 1 })", &Scope::default()),
+            evaluate_source("{ a = 1 }", &Scope::default())
+        );
+    }
+
+    #[test]
+    fn fold_conditionals() {
+        let scope = Scope {
+            count_index: Some(2),
+            variables: HashMap::from([(
+                "gender_field_type".to_string(),
+                Expression::String("safespot".to_string()),
+            )]),
+            ..Scope::default()
+        };
+
+        for (source, expected) in [
+            (
+                "var.gender_field_type == \"default\" ? 1 : 0",
+                Expression::Number(0.0),
+            ),
+            (
+                "var.gender_field_type == \"safespot\" ? 1 : 0",
+                Expression::Number(1.0),
+            ),
+            ("count.index * 2 + 1", Expression::Number(5.0)),
+            ("!(count.index < 2) && true", Expression::Boolean(true)),
+            (
+                "\"${var.gender_field_type}_${count.index}\"",
+                Expression::String("safespot_2".to_string()),
+            ),
+        ] {
+            assert_eq!(evaluate_source(source, &scope), expected, "{}", source);
+        }
+
+        let (_, unknown) = parse_expression("var.helpline == \"default\" ? 1 : 0").unwrap();
+        assert_eq!(evaluate(&unknown, &scope), unknown);
+    }
+}
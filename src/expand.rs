@@ -0,0 +1,450 @@
+use crate::{
+    ast::{
+        Block, Body, Expression, InstanceKey, Module, NestedBlock, Resource, ResourceDefinition,
+    },
+    eval::{self, Scope},
+};
+use std::{collections::HashMap, fmt};
+
+/// The meta-arguments creating several instances of a block
+const META_ARGUMENTS: [&str; 2] = ["count", "for_each"];
+
+#[derive(Debug, PartialEq)]
+pub enum ExpandError {
+    /// `for_each` isn't a map or a set of strings, or depends on values only known when applying
+    InvalidForEach {
+        block: String,
+    },
+    /// `count` isn't a whole number, or depends on values only known when applying
+    InvalidCount {
+        block: String,
+    },
+    CountAndForEach {
+        block: String,
+    },
+}
+
+impl fmt::Display for ExpandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExpandError::InvalidForEach { block } => write!(
+                f,
+                "the for_each of {} should be a map or a set of strings known before applying",
+                block
+            ),
+            ExpandError::InvalidCount { block } => write!(
+                f,
+                "the count of {} should be a whole number known before applying",
+                block
+            ),
+            ExpandError::CountAndForEach { block } => {
+                write!(f, "{} can't use both count and for_each", block)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExpandError {}
+
+/// Replaces every resource and module with `count` or `for_each` by its instances, with
+/// `count.index`, `each.key` and `each.value` substituted in their bodies. Blocks with a count of
+/// zero are dropped, and other blocks are kept as they are.
+///
+/// `variables` are only used to evaluate `count` and `for_each`, the bodies keep their `var.*`
+/// references so the generated configuration stays parameterized.
+pub fn expand(
+    blocks: Vec<Block>,
+    variables: &HashMap<String, Expression>,
+) -> Result<Vec<Block>, ExpandError> {
+    let mut expanded = vec![];
+
+    for block in blocks {
+        match block {
+            Block::Resource(resource) => {
+                expanded.extend(expand_resource(resource, variables)?.map(Block::Resource))
+            }
+            Block::Data(resource) => {
+                expanded.extend(expand_resource(resource, variables)?.map(Block::Data))
+            }
+            Block::Module(module) => {
+                expanded.extend(expand_module(module, variables)?.map(Block::Module))
+            }
+            block => expanded.push(block),
+        }
+    }
+
+    Ok(expanded)
+}
+
+fn expand_resource(
+    resource: Resource,
+    variables: &HashMap<String, Expression>,
+) -> Result<impl Iterator<Item = Resource>, ExpandError> {
+    let instances = instances(&resource.address(), &resource.res_def, variables)?;
+    let resources = match instances {
+        None => vec![resource],
+        Some(instances) => instances
+            .into_iter()
+            .map(|(key, scope)| Resource {
+                res_type: resource.res_type.clone(),
+                res_def: attributes(&resource.res_def, &scope),
+                res_name: resource.res_name.clone(),
+                res_blocks: nested_blocks(&resource.res_blocks, &scope),
+                comments: resource.comments.clone(),
+                key: Some(key),
+                module: resource.module.clone(),
+            })
+            .collect(),
+    };
+    Ok(resources.into_iter())
+}
+
+fn expand_module(
+    module: Module,
+    variables: &HashMap<String, Expression>,
+) -> Result<impl Iterator<Item = Module>, ExpandError> {
+    let instances = instances(&module.address(), &module.body.attributes, variables)?;
+    let modules = match instances {
+        None => vec![module],
+        Some(instances) => instances
+            .into_iter()
+            .map(|(key, scope)| Module {
+                name: module.name.clone(),
+                body: Body {
+                    attributes: attributes(&module.body.attributes, &scope),
+                    blocks: nested_blocks(&module.body.blocks, &scope),
+                    comments: module.body.comments.clone(),
+                },
+                key: Some(key),
+            })
+            .collect(),
+    };
+    Ok(modules.into_iter())
+}
+
+/// The key of every instance of a block with the scope of its body, or `None` when the block has
+/// neither `count` nor `for_each`
+fn instances(
+    address: &str,
+    attributes: &ResourceDefinition,
+    variables: &HashMap<String, Expression>,
+) -> Result<Option<Vec<(InstanceKey, Scope)>>, ExpandError> {
+    let meta_argument = |name: &str| {
+        attributes.iter().find(|(k, _)| k == name).map(|(_, v)| {
+            let scope = Scope {
+                variables: variables.clone(),
+                ..Scope::default()
+            };
+            eval::evaluate(v, &scope)
+        })
+    };
+
+    match (meta_argument("count"), meta_argument("for_each")) {
+        (None, None) => Ok(None),
+        (Some(_), Some(_)) => Err(ExpandError::CountAndForEach {
+            block: address.to_string(),
+        }),
+        (Some(count), None) => match count {
+            Expression::Number(n) if n >= 0.0 && n.fract() == 0.0 => Ok(Some(
+                (0..n as usize)
+                    .map(|i| {
+                        let scope = Scope {
+                            count_index: Some(i),
+                            ..Scope::default()
+                        };
+                        (InstanceKey::Index(i), scope)
+                    })
+                    .collect(),
+            )),
+            _ => Err(ExpandError::InvalidCount {
+                block: address.to_string(),
+            }),
+        },
+        (None, Some(for_each)) => {
+            let elements = elements(&for_each).ok_or_else(|| ExpandError::InvalidForEach {
+                block: address.to_string(),
+            })?;
+            Ok(Some(
+                elements
+                    .into_iter()
+                    .map(|(key, value)| {
+                        let scope = Scope {
+                            each: Some((Expression::String(key.clone()), value)),
+                            ..Scope::default()
+                        };
+                        (InstanceKey::Key(key), scope)
+                    })
+                    .collect(),
+            ))
+        }
+    }
+}
+
+/// The keys and values of a `for_each`, in declaration order
+fn elements(for_each: &Expression) -> Option<Vec<(String, Expression)>> {
+    match for_each {
+        Expression::FunctionCall { name, args, .. } if name == "toset" => match args.as_slice() {
+            [Expression::Tuple(items)] => items
+                .iter()
+                .map(|item| match item {
+                    Expression::String(s) => Some((s.clone(), item.clone())),
+                    _ => None,
+                })
+                .collect(),
+            _ => None,
+        },
+        Expression::Object(items) => items
+            .iter()
+            .map(|(key, value)| match key {
+                Expression::String(key) => Some((key.clone(), value.clone())),
+                _ => None,
+            })
+            .collect(),
+        _ => None,
+    }
+}
+
+/// The attributes of an instance, without the meta-arguments that created it
+fn attributes(attributes: &ResourceDefinition, scope: &Scope) -> ResourceDefinition {
+    attributes
+        .iter()
+        .filter(|(name, _)| !META_ARGUMENTS.contains(&name.as_str()))
+        .map(|(name, value)| (name.clone(), eval::evaluate(value, scope)))
+        .collect()
+}
+
+pub fn nested_blocks(blocks: &[NestedBlock], scope: &Scope) -> Vec<NestedBlock> {
+    blocks
+        .iter()
+        .map(|block| NestedBlock {
+            kind: block.kind.clone(),
+            labels: block.labels.clone(),
+            body: Body {
+                attributes: block
+                    .body
+                    .attributes
+                    .iter()
+                    .map(|(name, value)| (name.clone(), eval::evaluate(value, scope)))
+                    .collect(),
+                blocks: nested_blocks(&block.body.blocks, scope),
+                comments: block.body.comments.clone(),
+            },
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ast::TemplatePart,
+        json_utils::JsonValue,
+        parser::{only_resources, parse_blocks},
+    };
+
+    fn expand_source(
+        source: &str,
+        variables: &[(&str, Expression)],
+    ) -> Result<Vec<Block>, ExpandError> {
+        let (rest, blocks) = parse_blocks(source).unwrap();
+        assert_eq!(rest, "");
+        let variables = variables
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect();
+        expand(blocks, &variables)
+    }
+
+    fn addresses(blocks: &[Block]) -> Vec<String> {
+        blocks
+            .iter()
+            .filter_map(|block| match block {
+                Block::Resource(resource) => Some(resource.address()),
+                Block::Module(module) => Some(module.address()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn expand_sets() {
+        let blocks = expand_source(
+            r#"
+resource "twilio_autopilot_assistants_tasks_samples_v1" "goodbye_group" {
+  for_each    = toset(["no thanks", "bye", "no thanks"])
+  tagged_text = each.key
+  language    = "en-US"
+}
+"#,
+            &[],
+        )
+        .unwrap();
+
+        assert_eq!(
+            addresses(&blocks),
+            vec![
+                "twilio_autopilot_assistants_tasks_samples_v1.goodbye_group[\"no thanks\"]",
+                "twilio_autopilot_assistants_tasks_samples_v1.goodbye_group[\"bye\"]",
+            ]
+        );
+        assert_eq!(
+            only_resources(blocks)[1].res_def,
+            vec![
+                (
+                    "tagged_text".to_string(),
+                    Expression::String("bye".to_string())
+                ),
+                (
+                    "language".to_string(),
+                    Expression::String("en-US".to_string())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn expand_maps() {
+        let blocks = expand_source(
+            r#"
+resource "twilio_autopilot_assistants_field_types_field_values_v1" "gender" {
+  for_each   = { boy = "Boy", girl = "Girl" }
+  value      = each.value
+  synonym_of = null
+
+  lifecycle {
+    ignore_changes = ["${each.key}_value"]
+  }
+}
+"#,
+            &[],
+        )
+        .unwrap();
+        let instances = only_resources(blocks);
+
+        assert_eq!(instances.len(), 2);
+        assert_eq!(instances[0].key, Some(InstanceKey::Key("boy".to_string())));
+        assert_eq!(
+            instances[1].get("value"),
+            Some(&Expression::String("Girl".to_string()))
+        );
+        assert_eq!(
+            instances[1].res_blocks[0].body.attributes[0].1,
+            Expression::Tuple(vec![Expression::String("girl_value".to_string())])
+        );
+    }
+
+    #[test]
+    fn expand_counts() {
+        let source = r#"
+module "default_gender_field" {
+  count   = var.gender_field_type == "default" ? 1 : 0
+  source  = "../terraform-modules/gender-field/default"
+  bot_sid = twilio_autopilot_assistants_v1.pre_survey.sid
+}
+
+module "safespot_gender_field" {
+  count   = var.gender_field_type == "safespot" ? 1 : 0
+  source  = "../terraform-modules/gender-field/safespot"
+  bot_sid = twilio_autopilot_assistants_v1.pre_survey.sid
+}
+
+resource "twilio_autopilot_assistants_tasks_v1" "question" {
+  count       = 2
+  unique_name = "question_${count.index}"
+  actions     = jsonencode({ "say" : "Question ${count.index + 1} of ${var.questions}" })
+}
+"#;
+        let variables = [(
+            "gender_field_type",
+            Expression::String("safespot".to_string()),
+        )];
+
+        let blocks = expand_source(source, &variables).unwrap();
+
+        assert_eq!(
+            addresses(&blocks),
+            vec![
+                "module.safespot_gender_field[0]",
+                "twilio_autopilot_assistants_tasks_v1.question[0]",
+                "twilio_autopilot_assistants_tasks_v1.question[1]",
+            ]
+        );
+        match &blocks[0] {
+            Block::Module(module) => {
+                assert!(module.body.attributes.iter().all(|(k, _)| k != "count"))
+            }
+            block => panic!("expected a module, got {:?}", block),
+        }
+        let questions = only_resources(blocks);
+        assert_eq!(
+            questions[1].get("unique_name"),
+            Some(&Expression::String("question_1".to_string()))
+        );
+        // Variables are only substituted in the meta-arguments
+        assert_eq!(
+            questions[1].get("actions"),
+            Some(&Expression::Json(JsonValue::Object(HashMap::from([(
+                "say".to_string(),
+                JsonValue::Template(vec![
+                    TemplatePart::Literal("Question 2 of ".to_string()),
+                    TemplatePart::Interpolation(Expression::traversal("var", &["questions"])),
+                ])
+            )]))))
+        );
+    }
+
+    #[test]
+    fn report_invalid_meta_arguments() {
+        let error = |source: &str| expand_source(source, &[]).unwrap_err().to_string();
+
+        assert_eq!(
+            error(
+                r#"
+resource "twilio_autopilot_assistants_tasks_samples_v1" "greeting_group" {
+  for_each    = ["hi", "hello"]
+  tagged_text = each.key
+}
+"#
+            ),
+            "the for_each of twilio_autopilot_assistants_tasks_samples_v1.greeting_group should be a map or a set of strings known before applying"
+        );
+        assert_eq!(
+            error(
+                r#"
+module "default_gender_field" {
+  count  = var.gender_field_type == "default" ? 1 : 0
+  source = "../terraform-modules/gender-field/default"
+}
+"#
+            ),
+            "the count of module.default_gender_field should be a whole number known before applying"
+        );
+        assert_eq!(
+            error(
+                r#"
+resource "twilio_autopilot_assistants_v1" "survey" {
+  count    = 1
+  for_each = toset(["a"])
+}
+"#
+            ),
+            "twilio_autopilot_assistants_v1.survey can't use both count and for_each"
+        );
+    }
+
+    #[test]
+    fn keep_other_blocks() {
+        let source = r#"
+resource "twilio_autopilot_assistants_v1" "survey" {
+  unique_name = each.key
+}
+
+module "age_field" {
+  source = "../terraform-modules/age-field/default"
+}
+"#;
+        let (_, blocks) = parse_blocks(source).unwrap();
+
+        assert_eq!(expand(blocks.clone(), &HashMap::new()).unwrap(), blocks);
+    }
+}
@@ -0,0 +1,437 @@
+use crate::{
+    actions,
+    autopilot::{
+        Assistant, Defaults, FieldType, FieldValue, Model, ModelError, StyleSheet, Task,
+        TaskSample, Text, ASSISTANT, FIELD_TYPE, FIELD_VALUE, TASK, TASK_SAMPLE,
+    },
+    error::{Diagnostic, ParserError},
+    json_utils::{self, JsonValue},
+};
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+pub enum ExportError {
+    Parse(Diagnostic),
+    Model(ModelError),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::Parse(diagnostic) => write!(f, "{}", diagnostic),
+            ExportError::Model(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+/// Builds the model of an assistant out of its Autopilot export, e.g.
+///
+/// ```json
+/// {
+///   "uniqueName": "demo_chatbot",
+///   "fieldTypes": [{ "uniqueName": "Gender", "values": [{ "value": "Boy", "synonymOf": null }] }],
+///   "tasks": [{ "uniqueName": "greeting", "actions": { "actions": [] }, "samples": [] }]
+/// }
+/// ```
+///
+/// There are no Terraform resources to name the parts of the model after, so they are named
+/// after their unique names, e.g. `greeting` for the task and `greeting_0` for its first sample.
+/// The `fields` of the tasks are ignored, like their Terraform resources are.
+pub fn read_export(file: &str, source: &str) -> Result<Model, ExportError> {
+    let (rest, json) = json_utils::document::<ParserError<&str>>(source)
+        .map_err(|err| ExportError::Parse(Diagnostic::new(file, source, err)))?;
+    if !rest.is_empty() {
+        return Err(ExportError::Parse(Diagnostic::at(
+            file,
+            source,
+            rest,
+            "expected the end of the file".to_string(),
+        )));
+    }
+
+    model(&Entry {
+        json: &json,
+        path: String::new(),
+    })
+    .map_err(ExportError::Model)
+}
+
+fn model(assistant: &Entry) -> Result<Model, ModelError> {
+    let unique_name = assistant.string("uniqueName")?;
+    let name = resource_name(&unique_name);
+    let mut model = Model::default();
+
+    for field_type in assistant.items("fieldTypes")? {
+        let field_type_name = resource_name(&field_type.string("uniqueName")?);
+        for (i, value) in field_type.items("values")?.iter().enumerate() {
+            model.field_values.push(FieldValue {
+                name: format!("{}_{}", field_type_name, i),
+                address: format!("{}.{}_{}", FIELD_VALUE, field_type_name, i),
+                assistant: name.clone(),
+                field_type: field_type_name.clone(),
+                language: value.optional_string("language")?,
+                value: value.string("value")?,
+                synonym_of: value.optional_string("synonymOf")?,
+            });
+        }
+        model.field_types.push(FieldType {
+            address: format!("{}.{}", FIELD_TYPE, field_type_name),
+            name: field_type_name,
+            assistant: name.clone(),
+            unique_name: field_type.string("uniqueName")?,
+            comments: vec![],
+        });
+    }
+
+    for task in assistant.items("tasks")? {
+        let task_name = resource_name(&task.string("uniqueName")?);
+        for (i, sample) in task.items("samples")?.iter().enumerate() {
+            model.task_samples.push(TaskSample {
+                name: format!("{}_{}", task_name, i),
+                address: format!("{}.{}_{}", TASK_SAMPLE, task_name, i),
+                assistant: name.clone(),
+                task: task_name.clone(),
+                language: sample.optional_string("language")?,
+                tagged_text: sample.string("taggedText")?,
+            });
+        }
+        let actions = match task.json.get("actions") {
+            None | Some(JsonValue::Null) => vec![],
+            Some(json) => {
+                actions::parse_actions(json).map_err(|error| ModelError::InvalidActions {
+                    resource: task.resource(),
+                    error,
+                })?
+            }
+        };
+        model.tasks.push(Task {
+            address: format!("{}.{}", TASK, task_name),
+            name: task_name,
+            assistant: name.clone(),
+            unique_name: task.string("uniqueName")?,
+            actions,
+            comments: vec![],
+        });
+    }
+
+    let style_sheet = match assistant.json.get("styleSheet") {
+        None | Some(JsonValue::Null) => StyleSheet::default(),
+        Some(json) => StyleSheet::from_json(json).ok_or_else(|| {
+            assistant.mistyped("styleSheet", "a style sheet with a string say_voice")
+        })?,
+    };
+    let defaults = match assistant.json.get("defaults") {
+        None | Some(JsonValue::Null) => Defaults::default(),
+        Some(json) => Defaults::from_json(json)
+            .ok_or_else(|| assistant.mistyped("defaults", "defaults made of task URIs"))?,
+    };
+    let friendly_name = match assistant.json.get("friendlyName") {
+        None | Some(JsonValue::Null) => None,
+        Some(json) => Some(
+            Text::from_json(json).ok_or_else(|| assistant.mistyped("friendlyName", "a string"))?,
+        ),
+    };
+    model.assistants.push(Assistant {
+        address: format!("{}.{}", ASSISTANT, name),
+        name,
+        unique_name,
+        friendly_name,
+        style_sheet,
+        defaults,
+        log_queries: match assistant.json.get("logQueries") {
+            None | Some(JsonValue::Null) => false,
+            Some(JsonValue::Boolean(b)) => *b,
+            Some(_) => return Err(assistant.mistyped("logQueries", "a boolean")),
+        },
+        comments: vec![],
+    });
+
+    model.check_names()?;
+    model.check_synonyms()?;
+    Ok(model)
+}
+
+/// A valid Terraform name out of a unique name, which may hold any character
+fn resource_name(unique_name: &str) -> String {
+    let name: String = unique_name
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c,
+            false => '_',
+        })
+        .collect();
+    match name.starts_with(|c: char| c.is_ascii_digit()) {
+        true => format!("_{}", name),
+        false => name,
+    }
+}
+
+/// Typed access to a part of the export, failing with where it is, e.g. `tasks[2]`
+struct Entry<'a> {
+    json: &'a JsonValue,
+    /// Empty for the assistant itself
+    path: String,
+}
+
+impl<'a> Entry<'a> {
+    fn resource(&self) -> String {
+        match self.path.as_str() {
+            "" => "the assistant".to_string(),
+            path => path.to_string(),
+        }
+    }
+
+    fn missing(&self, property: &'static str) -> ModelError {
+        ModelError::MissingProperty {
+            resource: self.resource(),
+            property,
+        }
+    }
+
+    fn mistyped(&self, property: &'static str, expected: &'static str) -> ModelError {
+        ModelError::MistypedProperty {
+            resource: self.resource(),
+            property,
+            expected,
+        }
+    }
+
+    fn optional_string(&self, property: &'static str) -> Result<Option<String>, ModelError> {
+        match self.json.get(property) {
+            None | Some(JsonValue::Null) => Ok(None),
+            Some(JsonValue::Str(s)) => Ok(Some(s.clone())),
+            Some(_) => Err(self.mistyped(property, "a string")),
+        }
+    }
+
+    fn string(&self, property: &'static str) -> Result<String, ModelError> {
+        self.optional_string(property)?
+            .ok_or_else(|| self.missing(property))
+    }
+
+    /// The items of an array, which may be missing when there are none
+    fn items(&self, property: &'static str) -> Result<Vec<Entry<'a>>, ModelError> {
+        match self.json.get(property) {
+            None | Some(JsonValue::Null) => Ok(vec![]),
+            Some(JsonValue::Array(items)) => Ok(items
+                .iter()
+                .enumerate()
+                .map(|(i, json)| Entry {
+                    json,
+                    path: match self.path.as_str() {
+                        "" => format!("{}[{}]", property, i),
+                        path => format!("{}.{}[{}]", path, property, i),
+                    },
+                })
+                .collect()),
+            Some(_) => Err(self.mistyped(property, "an array")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        actions::{Action, Redirect},
+        expand::expand,
+        lex,
+        moved::Moves,
+        parser::{only_resources, parse_blocks},
+    };
+    use std::collections::HashMap;
+
+    const EXPORT: &str = r#"{
+  "friendlyName": "A bot",
+  "logQueries": true,
+  "uniqueName": "demo_chatbot",
+  "defaults": { "defaults": { "assistant_initiation": "", "fallback": "task://fallback" } },
+  "styleSheet": { "style_sheet": { "voice": { "say_voice": "Polly.Matthew" } } },
+  "fieldTypes": [
+    {
+      "uniqueName": "Gender",
+      "values": [
+        { "language": "en-US", "value": "Boy", "synonymOf": null },
+        { "language": "en-US", "value": "male", "synonymOf": "Boy" }
+      ]
+    }
+  ],
+  "tasks": [
+    {
+      "uniqueName": "greeting",
+      "actions": {
+        "actions": [
+          { "collect": { "name": "survey", "questions": [
+            { "question": "Are you a boy or a girl?", "name": "gender", "type": "Gender" }
+          ] } }
+        ]
+      },
+      "fields": [],
+      "samples": [
+        { "language": "en-US", "taggedText": "hi" },
+        { "language": "en-US", "taggedText": "hello" }
+      ]
+    },
+    {
+      "uniqueName": "fallback",
+      "actions": { "actions": [{ "say": "Sorry?" }, { "listen": true }] },
+      "fields": [],
+      "samples": []
+    }
+  ],
+  "modelBuild": { "uniqueName": "v1" }
+}"#;
+
+    /// The Terraform resources the export was made out of
+    const TERRAFORM: &str = r#"
+resource "twilio_autopilot_assistants_v1" "demo_chatbot" {
+  friendly_name = "A bot"
+  unique_name   = "demo_chatbot"
+  log_queries   = true
+  defaults      = jsonencode({ "defaults" : { "assistant_initiation" : "", "fallback" : "task://fallback" } })
+  style_sheet   = jsonencode({ "style_sheet" : { "voice" : { "say_voice" : "Polly.Matthew" } } })
+}
+
+resource "twilio_autopilot_assistants_field_types_v1" "Gender" {
+  unique_name   = "Gender"
+  assistant_sid = twilio_autopilot_assistants_v1.demo_chatbot.sid
+}
+
+resource "twilio_autopilot_assistants_field_types_field_values_v1" "Gender_values" {
+  for_each       = toset(["Boy"])
+  assistant_sid  = twilio_autopilot_assistants_v1.demo_chatbot.sid
+  field_type_sid = twilio_autopilot_assistants_field_types_v1.Gender.sid
+  language       = "en-US"
+  value          = each.key
+}
+
+resource "twilio_autopilot_assistants_field_types_field_values_v1" "Boy_synonyms" {
+  for_each       = toset(["male"])
+  assistant_sid  = twilio_autopilot_assistants_v1.demo_chatbot.sid
+  field_type_sid = twilio_autopilot_assistants_field_types_v1.Gender.sid
+  language       = "en-US"
+  value          = each.key
+  synonym_of     = "Boy"
+}
+
+resource "twilio_autopilot_assistants_tasks_v1" "greeting" {
+  unique_name   = "greeting"
+  assistant_sid = twilio_autopilot_assistants_v1.demo_chatbot.sid
+  actions = jsonencode({
+    "actions" : [
+      { "collect" : { "name" : "survey", "questions" : [
+        { "question" : "Are you a boy or a girl?", "name" : "gender", "type" : "Gender" }
+      ] } }
+    ]
+  })
+}
+
+resource "twilio_autopilot_assistants_tasks_samples_v1" "greeting_group" {
+  for_each      = toset(["hi", "hello"])
+  assistant_sid = twilio_autopilot_assistants_v1.demo_chatbot.sid
+  task_sid      = twilio_autopilot_assistants_tasks_v1.greeting.sid
+  language      = "en-US"
+  tagged_text   = each.key
+}
+
+resource "twilio_autopilot_assistants_tasks_v1" "fallback" {
+  unique_name   = "fallback"
+  assistant_sid = twilio_autopilot_assistants_v1.demo_chatbot.sid
+  actions       = jsonencode({ "actions" : [{ "say" : "Sorry?" }, { "listen" : true }] })
+}
+"#;
+
+    #[test]
+    fn read_the_export() {
+        let model = read_export("export.json", EXPORT).unwrap();
+
+        let assistant = &model.assistants[0];
+        assert_eq!(assistant.name, "demo_chatbot");
+        assert_eq!(assistant.style_sheet.voice.as_deref(), Some("Matthew"));
+        assert_eq!(
+            assistant.defaults.fallback.as_deref(),
+            Some("task://fallback")
+        );
+        assert!(assistant.log_queries);
+
+        assert_eq!(model.tasks.len(), 2);
+        assert_eq!(model.task_samples[1].name, "greeting_1");
+        assert_eq!(model.task_samples[1].task, "greeting");
+        assert_eq!(model.field_values[1].synonym_of.as_deref(), Some("Boy"));
+    }
+
+    #[test]
+    fn generate_like_terraform() {
+        let (_, blocks) = parse_blocks(TERRAFORM).unwrap();
+        let blocks = expand(blocks, &HashMap::new()).unwrap();
+        let terraform = Model::from_resources(&only_resources(blocks)).unwrap();
+        let export = read_export("export.json", EXPORT).unwrap();
+
+        assert_eq!(
+            lex::generate(&export, &Moves::default()).to_hcl(),
+            lex::generate(&terraform, &Moves::default()).to_hcl()
+        );
+    }
+
+    #[test]
+    fn read_plain_json() {
+        let model = read_export(
+            "export.json",
+            r#"{
+  "uniqueName": "bot",
+  "tasks": [{
+    "uniqueName": "price",
+    "actions": { "actions": [
+      { "say": "It costs ${price}" },
+      { "redirect": "https:\/\/example.com\/price" }
+    ] },
+    "samples": [{ "taggedText": "hi ${x}" }]
+  }]
+}"#,
+        )
+        .unwrap();
+
+        assert_eq!(model.task_samples[0].tagged_text, "hi ${x}");
+        assert_eq!(
+            model.tasks[0].actions,
+            vec![
+                Action::Say(Text::Literal("It costs ${price}".to_string())),
+                Action::Redirect(Redirect::Url {
+                    uri: Text::Literal("https://example.com/price".to_string()),
+                    method: None,
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn name_after_unique_names() {
+        assert_eq!(resource_name("pre-survey.v2"), "pre_survey_v2");
+        assert_eq!(resource_name("2nd"), "_2nd");
+    }
+
+    #[test]
+    fn report_invalid_exports() {
+        let error =
+            read_export("export.json", r#"{ "uniqueName": "bot", "tasks": [{}] }"#).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "tasks[0] is missing the property \"uniqueName\""
+        );
+
+        let error = read_export(
+            "export.json",
+            r#"{ "uniqueName": "bot", "fieldTypes": [{ "uniqueName": "a", "values": [{ "value": 1 }] }] }"#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the property \"value\" of fieldTypes[0].values[0] should be a string"
+        );
+
+        let error = read_export("export.json", "{ \"uniqueName\": ").unwrap_err();
+        assert!(matches!(error, ExportError::Parse(_)));
+    }
+}
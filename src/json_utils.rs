@@ -1,10 +1,14 @@
+use crate::{
+    ast::TemplatePart,
+    parser::{comment, literal_template, parse_template},
+};
 use nom::{
     branch::alt,
-    bytes::complete::{escaped, tag, take_while},
-    character::complete::{char, one_of},
-    combinator::{cut, map, opt, value},
-    error::{context, ContextError, ParseError},
-    multi::separated_list0,
+    bytes::complete::{escaped, tag, take_while1, take_while_m_n},
+    character::complete::{char, multispace0, one_of, satisfy},
+    combinator::{cut, map, map_opt, opt, recognize, value},
+    error::{context, ContextError, ErrorKind, ParseError},
+    multi::{fold_many0, many0_count, separated_list0},
     number::complete::double,
     sequence::{delimited, preceded, separated_pair, terminated},
     IResult,
@@ -12,7 +16,7 @@ use nom::{
 use std::collections::HashMap;
 // use std::str;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum JsonValue {
     Null,
     Str(String),
@@ -20,17 +24,51 @@ pub enum JsonValue {
     Num(f64),
     Array(Vec<JsonValue>),
     Object(HashMap<String, JsonValue>),
+    /// A string with `${...}` interpolations
+    Template(Vec<TemplatePart>),
+}
+
+impl JsonValue {
+    /// Looks up `key` if this value is an object
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(o) => o.get(key),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Calls `visit` with the parts of every template in this value
+    pub fn walk_templates<'a>(&'a self, visit: &mut impl FnMut(&'a [TemplatePart])) {
+        match self {
+            JsonValue::Template(parts) => visit(parts),
+            JsonValue::Array(values) => values.iter().for_each(|v| v.walk_templates(visit)),
+            JsonValue::Object(object) => object.values().for_each(|v| v.walk_templates(visit)),
+            _ => {}
+        }
+    }
 }
 
 /// parser combinators are constructed from the bottom up:
 /// first we write parsers for the smallest elements (here a space character),
 /// then we'll combine them in larger parsers
-fn sp<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, &'a str, E> {
+///
+/// The bodies of `jsonencode(...)` are HCL, so comments are skipped as whitespace too
+pub fn sp<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, &'a str, E> {
     let chars = " \t\r\n";
 
     // nom combinators like `take_while` return a function. That function is the
     // parser,to which we can pass the input
-    take_while(move |c| chars.contains(c))(i)
+    recognize(many0_count(alt((
+        take_while1(move |c| chars.contains(c)),
+        comment,
+    ))))(i)
 }
 
 /// A nom parser has the following signature:
@@ -49,7 +87,11 @@ fn sp<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, &'a str, E> {
 /// of the input data. and there is no allocation needed. This is the main idea
 /// behind nom's performance.
 pub fn parse_str<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, &'a str, E> {
-    escaped(take_while(|c| c != '"'), '\\', one_of("\"n\\"))(i)
+    escaped(
+        take_while1(|c| c != '"' && c != '\\'),
+        '\\',
+        one_of("\"n\\"),
+    )(i)
 }
 
 /// `tag(string)` generates a parser that recognizes the argument string.
@@ -84,11 +126,11 @@ fn null<'a, E: ParseError<&'a str>>(input: &'a str) -> IResult<&'a str, (), E> {
 ///
 /// `context` and `cut` are related to error management:
 /// - `cut` transforms an `Err::Error(e)` in `Err::Failure(e)`, signaling to
-/// combinators like  `alt` that they should not try other parsers. We were in the
-/// right branch (since we found the `"` character) but encountered an error when
-/// parsing the string
+///   combinators like  `alt` that they should not try other parsers. We were in the
+///   right branch (since we found the `"` character) but encountered an error when
+///   parsing the string
 /// - `context` lets you add a static string to provide more information in the
-/// error chain (to indicate which parser had an error)
+///   error chain (to indicate which parser had an error)
 pub fn string<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     i: &'a str,
 ) -> IResult<&'a str, &'a str, E> {
@@ -98,6 +140,21 @@ pub fn string<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     )(i)
 }
 
+/// the strings in `jsonencode(...)` bodies are HCL templates, which may hold `${...}`
+/// interpolations. Plain strings are still parsed into `JsonValue::Str`
+fn template<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, JsonValue, E> {
+    let (i, parts) = context("string", |i| {
+        parse_template(i).map_err(|err| err.map(|e| E::from_error_kind(e.input, e.kind)))
+    })(i)?;
+
+    match literal_template(&parts) {
+        Some(s) => Ok((i, JsonValue::Str(s))),
+        None => Ok((i, JsonValue::Template(parts))),
+    }
+}
+
 /// some combinators, like `separated_list0` or `many0`, will call a parser repeatedly,
 /// accumulating results in a `Vec`, until it encounters an error.
 /// If you want more control on the parser application, check out the `iterator`
@@ -159,7 +216,7 @@ pub fn json_value<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
         alt((
             map(hash, JsonValue::Object),
             map(array, JsonValue::Array),
-            map(string, |s| JsonValue::Str(String::from(s))),
+            template,
             map(double, JsonValue::Num),
             map(boolean, JsonValue::Boolean),
             map(null, |_| JsonValue::Null),
@@ -182,6 +239,123 @@ pub fn root<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     )(i)
 }
 
+/// The escapes of JSON strings, after their `\\`
+pub fn escape<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, char, E> {
+    alt((
+        value('"', char('"')),
+        value('\\', char('\\')),
+        value('/', char('/')),
+        value('\u{8}', char('b')),
+        value('\u{c}', char('f')),
+        value('\n', char('n')),
+        value('\r', char('r')),
+        value('\t', char('t')),
+        preceded(char('u'), unicode),
+    ))(i)
+}
+
+/// `\uXXXX` are UTF-16 code units, so the characters out of the BMP are written as a pair of
+/// surrogates, e.g. `\ud83d\ude00`
+fn unicode<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, char, E> {
+    fn code_unit<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, u32, E> {
+        map_opt(
+            take_while_m_n(4, 4, |c: char| c.is_ascii_hexdigit()),
+            |hex| u32::from_str_radix(hex, 16).ok(),
+        )(i)
+    }
+
+    let (i, unit) = code_unit(i)?;
+    match unit {
+        0xD800..=0xDBFF => map_opt(preceded(tag("\\u"), code_unit), |low| match low {
+            0xDC00..=0xDFFF => char::from_u32(0x10000 + ((unit - 0xD800) << 10) + (low - 0xDC00)),
+            _ => None,
+        })(i),
+        _ => match char::from_u32(unit) {
+            Some(c) => Ok((i, c)),
+            None => Err(nom::Err::Error(E::from_error_kind(i, ErrorKind::Char))),
+        },
+    }
+}
+
+/// A JSON string, unescaped, without templates
+pub fn plain_string<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, String, E> {
+    context(
+        "string",
+        preceded(
+            char('"'),
+            cut(terminated(
+                fold_many0(
+                    alt((
+                        preceded(char('\\'), cut(escape)),
+                        satisfy(|c| c != '"' && c != '\\'),
+                    )),
+                    String::new,
+                    |mut s, c| {
+                        s.push(c);
+                        s
+                    },
+                ),
+                char('"'),
+            )),
+        ),
+    )(i)
+}
+
+/// A JSON value as JSON reads it, unlike `json_value`: no comments, and no templates in strings
+fn plain_value<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, JsonValue, E> {
+    let member = separated_pair(
+        preceded(multispace0, plain_string),
+        cut(preceded(multispace0, char(':'))),
+        plain_value,
+    );
+    preceded(
+        multispace0,
+        alt((
+            context(
+                "map",
+                preceded(
+                    char('{'),
+                    cut(terminated(
+                        map(
+                            separated_list0(preceded(multispace0, char(',')), member),
+                            |members| JsonValue::Object(members.into_iter().collect()),
+                        ),
+                        preceded(multispace0, char('}')),
+                    )),
+                ),
+            ),
+            context(
+                "array",
+                preceded(
+                    char('['),
+                    cut(terminated(
+                        map(
+                            separated_list0(preceded(multispace0, char(',')), plain_value),
+                            JsonValue::Array,
+                        ),
+                        preceded(multispace0, char(']')),
+                    )),
+                ),
+            ),
+            map(plain_string, JsonValue::Str),
+            map(double, JsonValue::Num),
+            map(boolean, JsonValue::Boolean),
+            map(null, |_| JsonValue::Null),
+        )),
+    )(i)
+}
+
+/// A JSON document that isn't Terraform's, e.g. an Autopilot export
+pub fn document<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, JsonValue, E> {
+    terminated(plain_value, multispace0)(i)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -218,4 +392,41 @@ mod tests {
 
         assert!(root::<(&str, ErrorKind)>(data).is_ok());
     }
+
+    #[test]
+    fn parse_plain_documents() {
+        let data =
+            r#"{ "url": "https:\/\/example.com\b\f", "say": "${price} \ud83d\ude00 \u00e9" }"#;
+
+        let (rest, value) = document::<(&str, ErrorKind)>(data).unwrap();
+
+        assert_eq!(rest, "");
+        assert_eq!(
+            value.get("url").and_then(JsonValue::as_str),
+            Some("https://example.com\u{8}\u{c}")
+        );
+        assert_eq!(
+            value.get("say").and_then(JsonValue::as_str),
+            Some("${price} \u{1f600} \u{e9}")
+        );
+        assert!(document::<(&str, ErrorKind)>(r#""\ud83d""#).is_err());
+        assert!(document::<(&str, ErrorKind)>("{ // comment\n }").is_err());
+    }
+
+    #[test]
+    fn parse_comments() {
+        let data = "{
+            // a line comment
+            \"a\" : 42, # another one
+            \"b\" : /* a block comment */ \"https://example.com\"
+          }";
+
+        let (rest, value) = root::<(&str, ErrorKind)>(data).unwrap();
+
+        assert_eq!(rest, "");
+        assert_eq!(
+            value.get("b").and_then(JsonValue::as_str),
+            Some("https://example.com")
+        );
+    }
 }
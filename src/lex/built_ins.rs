@@ -0,0 +1,77 @@
+/// Twilio built-in field types and the Amazon built-in slot types that recognize the same values
+const AMAZON_SLOT_TYPES: &[(&str, &str)] = &[
+    ("Twilio.ALPHANUMERIC", "AMAZON.AlphaNumeric"),
+    ("Twilio.CITY", "AMAZON.City"),
+    ("Twilio.COUNTRY", "AMAZON.Country"),
+    ("Twilio.DATE", "AMAZON.Date"),
+    ("Twilio.DURATION", "AMAZON.Duration"),
+    ("Twilio.EMAIL", "AMAZON.EmailAddress"),
+    ("Twilio.FIRST_NAME", "AMAZON.FirstName"),
+    ("Twilio.LAST_NAME", "AMAZON.LastName"),
+    ("Twilio.NUMBER", "AMAZON.Number"),
+    ("Twilio.PERCENTAGE", "AMAZON.Percentage"),
+    ("Twilio.PHONE_NUMBER", "AMAZON.PhoneNumber"),
+    ("Twilio.STREET_ADDRESS", "AMAZON.StreetName"),
+    ("Twilio.TIME", "AMAZON.Time"),
+    ("Twilio.US_STATE", "AMAZON.State"),
+];
+
+/// A Twilio built-in field type without an Amazon equivalent, which gets a custom slot type
+pub struct CustomBuiltIn {
+    pub field_type: &'static str,
+    /// The name of the slot type in the bot
+    pub name: &'static str,
+    /// Values with their synonyms
+    pub values: &'static [(&'static str, &'static [&'static str])],
+}
+
+const CUSTOM_BUILT_INS: &[CustomBuiltIn] = &[CustomBuiltIn {
+    field_type: "Twilio.YES_NO",
+    name: "YesNo",
+    values: &[
+        (
+            "Yes",
+            &[
+                "yeah",
+                "yep",
+                "yup",
+                "sure",
+                "ok",
+                "okay",
+                "correct",
+                "right",
+                "of course",
+            ],
+        ),
+        ("No", &["nope", "nah", "not really", "incorrect", "no way"]),
+    ],
+}];
+
+/// The Amazon slot type recognizing the values of a Twilio built-in, e.g. `AMAZON.Number` for
+/// `Twilio.NUMBER`
+pub fn amazon_slot_type(field_type: &str) -> Option<&'static str> {
+    AMAZON_SLOT_TYPES
+        .iter()
+        .find(|(twilio, _)| *twilio == field_type)
+        .map(|(_, amazon)| *amazon)
+}
+
+pub fn custom_built_in(field_type: &str) -> Option<&'static CustomBuiltIn> {
+    CUSTOM_BUILT_INS.iter().find(|c| c.field_type == field_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_built_ins() {
+        assert_eq!(amazon_slot_type("Twilio.NUMBER"), Some("AMAZON.Number"));
+        assert_eq!(amazon_slot_type("Twilio.YES_NO"), None);
+        assert_eq!(
+            custom_built_in("Twilio.YES_NO").map(|c| c.name),
+            Some("YesNo")
+        );
+        assert!(custom_built_in("Twilio.NUMBER").is_none());
+    }
+}
@@ -0,0 +1,184 @@
+use super::{
+    hcl::{self, HclBlock, HclValue},
+    LEX_BOT, LEX_BOT_LOCALE,
+};
+use crate::{ast::Expression, autopilot::Text};
+
+/// The handler of every bot, written next to the generated configuration
+pub const HANDLER_FILE: &str = "lex_code_hook.js";
+pub const HANDLER: &str = include_str!("lex_code_hook.js");
+
+const LAMBDA_FUNCTION: &str = "aws_lambda_function";
+const LAMBDA_PERMISSION: &str = "aws_lambda_permission";
+const LEX_BOT_VERSION: &str = "aws_lexv2models_bot_version";
+/// The AWS provider has no Lex V2 bot alias, the Cloud Control one does
+const LEX_BOT_ALIAS: &str = "awscc_lex_bot_alias";
+const ARCHIVE: &str = "lex_code_hook";
+
+/// A URL Autopilot sent requests to
+#[derive(Debug, Clone, PartialEq)]
+pub struct Endpoint {
+    pub url: Text,
+    pub method: Option<String>,
+}
+
+/// Where the code hook forwards the requests of an intent
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    /// The Lex name of the intent
+    pub intent: String,
+    /// The URL the task redirected to, called on fulfillment
+    pub redirect: Option<Endpoint>,
+    /// The webhooks validating the answers to the questions, by slot name
+    pub webhooks: Vec<(String, Endpoint)>,
+    /// What the task remembered before redirecting, as session attributes
+    pub remember: Vec<(String, Expression)>,
+}
+
+/// The role of the Lambda functions, and the archive of their handler, shared by the bots
+pub fn generate_shared() -> Vec<HclBlock> {
+    vec![
+        HclBlock::new("variable")
+            .label("lambda_role_arn")
+            .attr(
+                "description",
+                HclValue::str("IAM role assumed by the Lambda functions of the Lex code hooks"),
+            )
+            .attr("type", HclValue::raw("string")),
+        HclBlock::new("data")
+            .label("archive_file")
+            .label(ARCHIVE)
+            .attr("type", HclValue::str("zip"))
+            .attr(
+                "source_file",
+                HclValue::raw(format!("\"${{path.module}}/{}\"", HANDLER_FILE)),
+            )
+            .attr(
+                "output_path",
+                HclValue::raw(format!("\"${{path.module}}/{}.zip\"", ARCHIVE)),
+            ),
+    ]
+}
+
+/// The Lambda function forwarding the code hooks of the bot to the Autopilot URLs, and the bot
+/// alias invoking it. An alias needs a version, built once `depends_on` is.
+pub fn generate(
+    bot: &str,
+    unique_name: &str,
+    routes: &[Route],
+    depends_on: &[String],
+) -> Vec<HclBlock> {
+    let function = HclBlock::resource(LAMBDA_FUNCTION, bot)
+        .attr(
+            "function_name",
+            HclValue::str(format!("{}-code-hook", unique_name)),
+        )
+        .attr("role", HclValue::raw("var.lambda_role_arn"))
+        .attr("runtime", HclValue::str("nodejs18.x"))
+        .attr("handler", HclValue::str(format!("{}.handler", ARCHIVE)))
+        .attr(
+            "filename",
+            HclValue::raw(format!("data.archive_file.{}.output_path", ARCHIVE)),
+        )
+        .attr(
+            "source_code_hash",
+            HclValue::raw(format!("data.archive_file.{}.output_base64sha256", ARCHIVE)),
+        )
+        .block(HclBlock::new("environment").attr(
+            "variables",
+            HclValue::raw(format!(
+                "{{ ROUTES = jsonencode({}) }}",
+                hcl::expression(&routes_expression(routes))
+            )),
+        ));
+
+    let permission = HclBlock::resource(LAMBDA_PERMISSION, bot)
+        .attr("statement_id", HclValue::str("AllowLexInvoke"))
+        .attr("action", HclValue::str("lambda:InvokeFunction"))
+        .attr(
+            "function_name",
+            HclValue::raw(format!("{}.{}.function_name", LAMBDA_FUNCTION, bot)),
+        )
+        .attr("principal", HclValue::str("lexv2.amazonaws.com"))
+        .attr(
+            "source_arn",
+            HclValue::raw(format!("{}.{}.arn", LEX_BOT_ALIAS, bot)),
+        );
+
+    let locale_id = format!("{}.{}.locale_id", LEX_BOT_LOCALE, bot);
+    let version = HclBlock::resource(LEX_BOT_VERSION, bot)
+        .attr("bot_id", HclValue::raw(format!("{}.{}.id", LEX_BOT, bot)))
+        .attr(
+            "locale_specification",
+            HclValue::raw(format!(
+                "{{ ({}) = {{ source_bot_version = \"DRAFT\" }} }}",
+                locale_id
+            )),
+        )
+        .attr(
+            "depends_on",
+            HclValue::raw(format!("[{}]", depends_on.join(", "))),
+        );
+
+    let alias = HclBlock::resource(LEX_BOT_ALIAS, bot)
+        .attr("bot_alias_name", HclValue::str("live"))
+        .attr("bot_id", HclValue::raw(format!("{}.{}.id", LEX_BOT, bot)))
+        .attr(
+            "bot_version",
+            HclValue::raw(format!("{}.{}.bot_version", LEX_BOT_VERSION, bot)),
+        )
+        .attr(
+            "bot_alias_locale_settings",
+            HclValue::raw(format!(
+                "[{{ locale_id = {}, bot_alias_locale_setting = {{ enabled = true, \
+                 code_hook_specification = {{ lambda_code_hook = {{ \
+                 code_hook_interface_version = \"1.0\", lambda_arn = {}.{}.arn }} }} }} }}]",
+                locale_id, LAMBDA_FUNCTION, bot
+            )),
+        );
+
+    vec![function, permission, version, alias]
+}
+
+/// The `ROUTES` of the handler, e.g.
+/// `{ redirect_function = { url = "${var.serverless_url}/autopilotRedirect", method = "POST" } }`
+fn routes_expression(routes: &[Route]) -> Expression {
+    let key = |s: &str| Expression::String(s.to_string());
+    let endpoint = |endpoint: &Endpoint| {
+        let url = match &endpoint.url {
+            Text::Literal(url) => Expression::String(url.clone()),
+            Text::Template(parts) => Expression::Template(parts.clone()),
+        };
+        let mut items = vec![(key("url"), url)];
+        if let Some(method) = &endpoint.method {
+            items.push((key("method"), Expression::String(method.clone())));
+        }
+        items
+    };
+
+    Expression::Object(
+        routes
+            .iter()
+            .map(|route| {
+                let mut items = route.redirect.as_ref().map(endpoint).unwrap_or_default();
+                if !route.webhooks.is_empty() {
+                    let webhooks = route
+                        .webhooks
+                        .iter()
+                        .map(|(slot, webhook)| (key(slot), Expression::Object(endpoint(webhook))))
+                        .collect();
+                    items.push((key("webhooks"), Expression::Object(webhooks)));
+                }
+                if !route.remember.is_empty() {
+                    let remember = route
+                        .remember
+                        .iter()
+                        .map(|(k, value)| (key(k), value.clone()))
+                        .collect();
+                    items.push((key("remember"), Expression::Object(remember)));
+                }
+                (key(&route.intent), Expression::Object(items))
+            })
+            .collect(),
+    )
+}
@@ -0,0 +1,427 @@
+use crate::{
+    ast::{Expression, TemplatePart, TraversalStep, UnaryOperator},
+    json_utils::JsonValue,
+};
+use std::fmt;
+
+/// The right hand side of an attribute in the generated configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HclValue {
+    Str(String),
+    Number(f64),
+    Boolean(bool),
+    /// An expression emitted verbatim, e.g. `aws_lexv2models_bot.pre_survey.id`
+    Raw(String),
+}
+
+impl HclValue {
+    pub fn str(s: impl Into<String>) -> Self {
+        HclValue::Str(s.into())
+    }
+
+    pub fn raw(s: impl Into<String>) -> Self {
+        HclValue::Raw(s.into())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HclBodyItem {
+    Attribute(String, HclValue),
+    Block(HclBlock),
+}
+
+/// A block of the generated configuration, e.g. `resource "type" "name" { ... }`.
+/// Built with the chained methods below and rendered through `Display`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HclBlock {
+    pub kind: String,
+    pub labels: Vec<String>,
+    pub body: Vec<HclBodyItem>,
+    /// Rendered as `//` comments right before the block
+    pub comments: Vec<String>,
+}
+
+impl HclBlock {
+    pub fn new(kind: &str) -> Self {
+        HclBlock {
+            kind: kind.to_string(),
+            labels: vec![],
+            body: vec![],
+            comments: vec![],
+        }
+    }
+
+    pub fn resource(res_type: &str, res_name: &str) -> Self {
+        HclBlock::new("resource").label(res_type).label(res_name)
+    }
+
+    pub fn label(mut self, label: &str) -> Self {
+        self.labels.push(label.to_string());
+        self
+    }
+
+    pub fn comments(mut self, comments: &[String]) -> Self {
+        self.comments.extend(comments.iter().cloned());
+        self
+    }
+
+    pub fn attr(mut self, key: &str, value: HclValue) -> Self {
+        self.body
+            .push(HclBodyItem::Attribute(key.to_string(), value));
+        self
+    }
+
+    pub fn block(mut self, block: HclBlock) -> Self {
+        self.body.push(HclBodyItem::Block(block));
+        self
+    }
+
+    pub fn blocks(mut self, blocks: impl IntoIterator<Item = HclBlock>) -> Self {
+        self.body.extend(blocks.into_iter().map(HclBodyItem::Block));
+        self
+    }
+
+    fn write_indented(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        let indent = "  ".repeat(depth);
+
+        for line in self.comments.iter().flat_map(|c| c.lines()) {
+            writeln!(f, "{}// {}", indent, line.trim())?;
+        }
+        write!(f, "{}{}", indent, self.kind)?;
+        for label in &self.labels {
+            write!(f, " \"{}\"", escape(label))?;
+        }
+        writeln!(f, " {{")?;
+
+        // Like `terraform fmt`, the `=` of consecutive attributes are aligned, and nested
+        // blocks are separated from their siblings by a blank line
+        let mut i = 0;
+        while i < self.body.len() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+
+            match &self.body[i] {
+                HclBodyItem::Attribute(..) => {
+                    let run: Vec<(&String, &HclValue)> = self.body[i..]
+                        .iter()
+                        .map_while(|item| match item {
+                            HclBodyItem::Attribute(k, v) => Some((k, v)),
+                            HclBodyItem::Block(_) => None,
+                        })
+                        .collect();
+                    let width = run.iter().map(|(k, _)| k.len()).max().unwrap_or(0);
+
+                    for (key, value) in &run {
+                        writeln!(f, "{}  {:width$} = {}", indent, key, value)?;
+                    }
+                    i += run.len();
+                }
+                HclBodyItem::Block(block) => {
+                    block.write_indented(f, depth + 1)?;
+                    i += 1;
+                }
+            }
+        }
+
+        writeln!(f, "{}}}", indent)
+    }
+}
+
+impl fmt::Display for HclBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_indented(f, 0)
+    }
+}
+
+impl fmt::Display for HclValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HclValue::Str(s) => write!(f, "\"{}\"", escape(s)),
+            HclValue::Number(n) => write!(f, "{}", n),
+            HclValue::Boolean(b) => write!(f, "{}", b),
+            HclValue::Raw(r) => write!(f, "{}", r),
+        }
+    }
+}
+
+/// Escapes a string so it can be written between double quotes in HCL, including the
+/// template sequences `${` and `%{` that would otherwise be interpolated
+pub fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace("${", "$${")
+        .replace("%{", "%%{")
+}
+
+/// Renders a string with interpolations back to its HCL source, e.g. `"${var.url}/redirect"`
+pub fn template(parts: &[TemplatePart]) -> String {
+    let parts: String = parts
+        .iter()
+        .map(|part| match part {
+            TemplatePart::Literal(s) => escape(s),
+            TemplatePart::Interpolation(value) => format!("${{{}}}", expression(value)),
+        })
+        .collect();
+
+    format!("\"{}\"", parts)
+}
+
+/// Renders a parsed expression back to its HCL source
+pub fn expression(value: &Expression) -> String {
+    match value {
+        Expression::Null => "null".to_string(),
+        Expression::Boolean(b) => b.to_string(),
+        Expression::Number(n) => n.to_string(),
+        Expression::String(s) => format!("\"{}\"", escape(s)),
+        Expression::Template(parts) => template(parts),
+        Expression::Json(json) => format!("jsonencode({})", json_expression(json)),
+        Expression::Tuple(items) => {
+            let items: Vec<String> = items.iter().map(expression).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Expression::Object(items) => {
+            let items: Vec<String> = items
+                .iter()
+                .map(|(k, v)| format!("{} = {}", object_key(k), expression(v)))
+                .collect();
+            format!("{{ {} }}", items.join(", "))
+        }
+        Expression::Identifier(name) => name.clone(),
+        Expression::Traversal(root, steps) => {
+            let steps: String = steps
+                .iter()
+                .map(|step| match step {
+                    TraversalStep::GetAttr(name) => format!(".{}", name),
+                    TraversalStep::Index(index) => format!("[{}]", expression(index)),
+                    TraversalStep::AttrSplat => ".*".to_string(),
+                    TraversalStep::FullSplat => "[*]".to_string(),
+                })
+                .collect();
+            format!("{}{}", operand(root, u8::MAX), steps)
+        }
+        Expression::FunctionCall {
+            name,
+            args,
+            expand_final,
+        } => {
+            let args: Vec<String> = args.iter().map(expression).collect();
+            let dots = if *expand_final { "..." } else { "" };
+            format!("{}({}{})", name, args.join(", "), dots)
+        }
+        Expression::Unary(op, operand_value) => {
+            let symbol = match op {
+                UnaryOperator::Not => "!",
+                UnaryOperator::Negate => "-",
+            };
+            format!("{}{}", symbol, operand(operand_value, u8::MAX))
+        }
+        Expression::Binary(op, lhs, rhs) => format!(
+            "{} {} {}",
+            operand(lhs, op.precedence()),
+            op.symbol(),
+            operand(rhs, op.precedence() + 1)
+        ),
+        Expression::Conditional {
+            condition,
+            then,
+            otherwise,
+        } => format!(
+            "{} ? {} : {}",
+            operand(condition, 0),
+            expression(then),
+            expression(otherwise)
+        ),
+        Expression::For(f) => {
+            let vars = match &f.key_var {
+                Some(key_var) => format!("{}, {}", key_var, f.value_var),
+                None => f.value_var.clone(),
+            };
+            let condition = match &f.condition {
+                Some(condition) => format!(" if {}", expression(condition)),
+                None => String::new(),
+            };
+            let head = format!("for {} in {} :", vars, expression(&f.collection));
+            match &f.key {
+                Some(key) => format!(
+                    "{{{} {} => {}{}{}}}",
+                    head,
+                    expression(key),
+                    expression(&f.value),
+                    if f.grouping { "..." } else { "" },
+                    condition
+                ),
+                None => format!("[{} {}{}]", head, expression(&f.value), condition),
+            }
+        }
+        Expression::Parenthesized(e) => format!("({})", expression(e)),
+    }
+}
+
+/// Renders an operand, wrapped in parentheses if it would otherwise bind looser than `precedence`
+fn operand(value: &Expression, precedence: u8) -> String {
+    let binds = match value {
+        Expression::Binary(op, ..) => op.precedence(),
+        Expression::Conditional { .. } => 0,
+        _ => u8::MAX,
+    };
+
+    match binds < precedence {
+        true => format!("({})", expression(value)),
+        false => expression(value),
+    }
+}
+
+/// Keys that are valid identifiers are written bare, the rest as any other expression
+fn object_key(key: &Expression) -> String {
+    match key {
+        Expression::String(s)
+            if s.starts_with(|c: char| c.is_alphabetic() || c == '_')
+                && s.chars()
+                    .all(|c| c.is_alphanumeric() || c == '_' || c == '-') =>
+        {
+            s.clone()
+        }
+        key => expression(key),
+    }
+}
+
+/// Renders the argument of a `jsonencode(...)` call, with the keys of objects sorted
+fn json_expression(json: &JsonValue) -> String {
+    match json {
+        JsonValue::Null => "null".to_string(),
+        JsonValue::Str(s) => format!("\"{}\"", escape(s)),
+        JsonValue::Boolean(b) => b.to_string(),
+        JsonValue::Num(n) => n.to_string(),
+        JsonValue::Array(values) => {
+            let values: Vec<String> = values.iter().map(json_expression).collect();
+            format!("[{}]", values.join(", "))
+        }
+        JsonValue::Object(object) => {
+            let mut keys: Vec<&String> = object.keys().collect();
+            keys.sort();
+            let entries: Vec<String> = keys
+                .into_iter()
+                .map(|k| format!("\"{}\" : {}", escape(k), json_expression(&object[k])))
+                .collect();
+            format!("{{ {} }}", entries.join(", "))
+        }
+        JsonValue::Template(parts) => template(parts),
+    }
+}
+
+/// Renders a list of top level blocks separated by a blank line
+pub fn render(blocks: &[HclBlock]) -> String {
+    blocks
+        .iter()
+        .map(|b| b.to_string())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_nested_blocks() {
+        let block = HclBlock::resource("aws_lexv2models_slot_type", "age")
+            .attr("name", HclValue::str("Age"))
+            .attr("bot_version", HclValue::str("DRAFT"))
+            .block(
+                HclBlock::new("slot_type_values")
+                    .block(HclBlock::new("sample_value").attr("value", HclValue::str("1"))),
+            );
+
+        let expected = "resource \"aws_lexv2models_slot_type\" \"age\" {
+  name        = \"Age\"
+  bot_version = \"DRAFT\"
+
+  slot_type_values {
+    sample_value {
+      value = \"1\"
+    }
+  }
+}
+";
+
+        assert_eq!(block.to_string(), expected);
+    }
+
+    #[test]
+    fn render_values() {
+        let block = HclBlock::new("locals")
+            .attr("reference", HclValue::raw("aws_lexv2models_bot.bot.id"))
+            .attr("threshold", HclValue::Number(0.4))
+            .attr("enabled", HclValue::Boolean(true));
+
+        let expected = "locals {
+  reference = aws_lexv2models_bot.bot.id
+  threshold = 0.4
+  enabled   = true
+}
+";
+
+        assert_eq!(block.to_string(), expected);
+    }
+
+    #[test]
+    fn render_comments() {
+        let block = HclBlock::new("locals")
+            .comments(&[
+                "Pre Survey".to_string(),
+                "first line\n * second line".to_string(),
+            ])
+            .attr("a", HclValue::Boolean(true));
+
+        let expected = "// Pre Survey
+// first line
+// * second line
+locals {
+  a = true
+}
+";
+
+        assert_eq!(block.to_string(), expected);
+    }
+
+    #[test]
+    fn render_template() {
+        let parts = vec![
+            TemplatePart::Interpolation(Expression::traversal("var", &["serverless_url"])),
+            TemplatePart::Literal("/autopilotRedirect?at=\"${here}\"".to_string()),
+        ];
+
+        assert_eq!(
+            template(&parts),
+            "\"${var.serverless_url}/autopilotRedirect?at=\\\"$${here}\\\"\""
+        );
+    }
+
+    #[test]
+    fn render_expressions() {
+        for source in [
+            "var.a == \"default\" ? 1 : 0",
+            "(var.a + var.b) * -var.c",
+            "!(var.a || var.b) && var.c",
+            "10 - (4 - 3)",
+            "[for name, task in var.tasks : upper(name) if task.enabled]",
+            "{for task in var.tasks : task.name => task.samples...}",
+            "concat([1, 2], local.extra...)[0]",
+            "aws_lexv2models_slot.questions[*].slot_id",
+            "{ source = \"twilio/twilio\", \"a b\" = null }",
+        ] {
+            let (_, value) = crate::parser::parse_expression(source).unwrap();
+            assert_eq!(expression(&value), source);
+        }
+    }
+
+    #[test]
+    fn escape_quotes_and_templates() {
+        assert_eq!(
+            escape("say \"hi\" to ${var.name}"),
+            "say \\\"hi\\\" to $${var.name}"
+        );
+    }
+}
@@ -0,0 +1,136 @@
+// Lex V2 code hook standing in for the Autopilot webhooks of a bot, generated by ap2lex-tf.
+//
+// ROUTES maps the intents to the URL their task redirected to, the webhooks validating their
+// slots, and what the task remembered before redirecting:
+// { "intent": { "url": "...", "method": "POST", "webhooks": { "slot": { ... } }, "remember": { ... } } }
+// The URLs get the parameters Autopilot sent them, and their answer is carried out as far as a
+// stub can: `say`, `remember` and `task://` redirects. Anything else needs porting by hand.
+const routes = JSON.parse(process.env.ROUTES || '{}');
+
+exports.handler = async (event) => {
+  const intent = event.sessionState.intent;
+  const route = routes[intent.name] || {};
+  event.sessionState.sessionAttributes = {
+    ...event.sessionState.sessionAttributes,
+    ...route.remember,
+  };
+  const parameters = autopilotParameters(event);
+
+  if (event.invocationSource === 'DialogCodeHook') {
+    for (const [name, webhook] of Object.entries(route.webhooks || {})) {
+      const slot = intent.slots[name];
+      if (!slot || !slot.value) {
+        continue;
+      }
+      const { valid } = await call(webhook, {
+        ...parameters,
+        ValidateFieldName: name,
+        ValidateFieldValue: slot.value.originalValue,
+      });
+      if (!valid) {
+        return {
+          sessionState: {
+            ...event.sessionState,
+            intent: { ...intent, slots: { ...intent.slots, [name]: null } },
+            dialogAction: { type: 'ElicitSlot', slotToElicit: name },
+          },
+        };
+      }
+    }
+    return { sessionState: { ...event.sessionState, dialogAction: { type: 'Delegate' } } };
+  }
+
+  if (!route.url) {
+    return close(event, event.sessionState.sessionAttributes, []);
+  }
+  const { actions } = await call(route, parameters);
+  return lexResponse(event, actions || []);
+};
+
+// The parameters of an Autopilot webhook request, from the Lex event. The answers of the intent
+// are collected under its name, and the session attributes make the memory.
+function autopilotParameters(event) {
+  const { intent, sessionAttributes = {} } = event.sessionState;
+
+  const memory = {};
+  for (const [key, value] of Object.entries(sessionAttributes)) {
+    try {
+      memory[key] = JSON.parse(value);
+    } catch {
+      memory[key] = value;
+    }
+  }
+  const answers = {};
+  for (const [name, slot] of Object.entries(intent.slots || {})) {
+    if (slot && slot.value) {
+      answers[name] = { answer: slot.value.interpretedValue, filled: true };
+    }
+  }
+  const twilio = memory.twilio || {};
+  memory.twilio = {
+    ...twilio,
+    collected_data: { ...twilio.collected_data, [intent.name]: { answers } },
+  };
+
+  return {
+    UserIdentifier: event.sessionId,
+    DialogueSid: event.sessionId,
+    Channel: 'lex',
+    CurrentTask: intent.name,
+    CurrentInput: event.inputTranscript || '',
+    Memory: JSON.stringify(memory),
+  };
+}
+
+async function call({ url, method = 'POST' }, parameters) {
+  const body = new URLSearchParams(parameters);
+  const response =
+    method.toUpperCase() === 'GET'
+      ? await fetch(`${url}?${body}`)
+      : await fetch(url, { method, body });
+  if (!response.ok) {
+    throw new Error(`${url} answered ${response.status}`);
+  }
+  return response.json();
+}
+
+function lexResponse(event, actions) {
+  const sessionAttributes = { ...event.sessionState.sessionAttributes };
+  const messages = [];
+  let redirect;
+  for (const action of actions) {
+    if (action.say) {
+      const content = typeof action.say === 'string' ? action.say : action.say.speech;
+      messages.push({ contentType: 'PlainText', content });
+    }
+    for (const [key, value] of Object.entries(action.remember || {})) {
+      sessionAttributes[key] = typeof value === 'string' ? value : JSON.stringify(value);
+    }
+    if (typeof action.redirect === 'string' && action.redirect.startsWith('task://')) {
+      redirect = action.redirect.slice('task://'.length);
+    }
+  }
+
+  if (redirect) {
+    return {
+      sessionState: {
+        sessionAttributes,
+        intent: { name: redirect, state: 'InProgress', slots: {} },
+        dialogAction: { type: 'Delegate' },
+      },
+      messages,
+    };
+  }
+  return close(event, sessionAttributes, messages);
+}
+
+function close(event, sessionAttributes, messages) {
+  return {
+    sessionState: {
+      sessionAttributes,
+      intent: { ...event.sessionState.intent, state: 'Fulfilled' },
+      dialogAction: { type: 'Close' },
+    },
+    messages,
+  };
+}
@@ -0,0 +1,1602 @@
+mod built_ins;
+mod code_hook;
+pub mod hcl;
+
+use crate::{
+    actions::{Action, Question, Redirect},
+    ast::{Expression, TemplatePart},
+    autopilot::{name_of, Assistant, FieldType, Model, Task, Text},
+    json_utils::JsonValue,
+    moved::{self, without_keys, Moves},
+};
+use built_ins::CustomBuiltIn;
+use code_hook::{Endpoint, Route};
+use hcl::{HclBlock, HclBodyItem, HclValue};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+const LEX_BOT: &str = "aws_lexv2models_bot";
+const LEX_BOT_LOCALE: &str = "aws_lexv2models_bot_locale";
+const LEX_INTENT: &str = "aws_lexv2models_intent";
+const LEX_SLOT_TYPE: &str = "aws_lexv2models_slot_type";
+const LEX_SLOT: &str = "aws_lexv2models_slot";
+const TERRAFORM_DATA: &str = "terraform_data";
+
+const DEFAULT_LOCALE: &str = "en_US";
+const DEFAULT_MAX_RETRIES: u32 = 2;
+const MAX_RETRIES: u32 = 5;
+/// The prompts of a slot, the first one and its re-prompts
+const MAX_MESSAGE_GROUPS: u32 = 5;
+
+/// The generated Lex configuration, plus whatever could not be carried over from Autopilot
+#[derive(Debug)]
+pub struct LexConfig {
+    pub blocks: Vec<HclBlock>,
+    pub warnings: Vec<String>,
+    /// Terraform variables interpolated in the Autopilot strings, declared in the Lex configuration too
+    pub variables: BTreeSet<String>,
+    /// What the conversion did differently from Autopilot, for the migration summary
+    pub summary: Vec<String>,
+    /// Files to write next to the configuration, by name, e.g. the handler of the code hooks
+    pub files: Vec<(String, String)>,
+    /// Where the memory keys of Autopilot end up, by key
+    pub memory_keys: BTreeMap<String, Vec<String>>,
+}
+
+impl LexConfig {
+    pub fn to_hcl(&self) -> String {
+        hcl::render(&self.blocks)
+    }
+}
+
+/// Generates the Amazon Lex V2 resources analogous to the given Autopilot ones.
+/// Each assistant becomes a bot with a single locale, holding an intent per task and a slot
+/// type per field type. The Autopilot resources renamed by `moves` get `moved` blocks for
+/// their Lex resources too.
+pub fn generate(model: &Model, moves: &Moves) -> LexConfig {
+    let mut config = LexConfig {
+        blocks: vec![HclBlock::new("variable")
+            .label("lex_role_arn")
+            .attr(
+                "description",
+                HclValue::str("IAM role assumed by the Lex bots"),
+            )
+            .attr("type", HclValue::raw("string"))],
+        warnings: vec![],
+        variables: BTreeSet::new(),
+        summary: vec![],
+        files: vec![],
+        memory_keys: BTreeMap::new(),
+    };
+
+    for assistant in &model.assistants {
+        generate_assistant(assistant, model, &mut config);
+    }
+
+    let has_assistant = |name: &String| model.assistants.iter().any(|a| a.name == *name);
+    let orphans = model
+        .tasks
+        .iter()
+        .filter(|t| !has_assistant(&t.assistant))
+        .map(|r| without_keys(&r.address))
+        .chain(
+            model
+                .task_samples
+                .iter()
+                .filter(|s| !has_assistant(&s.assistant))
+                .map(|r| without_keys(&r.address)),
+        )
+        .chain(
+            model
+                .field_types
+                .iter()
+                .filter(|f| !has_assistant(&f.assistant))
+                .map(|r| without_keys(&r.address)),
+        )
+        .chain(
+            model
+                .field_values
+                .iter()
+                .filter(|v| !has_assistant(&v.assistant))
+                .map(|r| without_keys(&r.address)),
+        );
+    for orphan in orphans {
+        config.warnings.push(format!(
+            "{} does not belong to any assistant, skipping it",
+            orphan
+        ));
+    }
+
+    if !config.files.is_empty() {
+        config.blocks.splice(1..1, code_hook::generate_shared());
+    }
+    config.blocks.extend(generate_moved(model, moves));
+
+    let variables = config.variables.iter().map(|name| {
+        HclBlock::new("variable")
+            .label(name)
+            .attr("type", HclValue::raw("string"))
+    });
+    config
+        .blocks
+        .splice(1..1, variables.collect::<Vec<HclBlock>>());
+
+    // The instances of a resource with `for_each` share their warnings, which name it without
+    // their keys
+    let mut reported = HashSet::new();
+    config.warnings.retain(|w| reported.insert(w.clone()));
+
+    config
+}
+
+/// A text, keeping its interpolations and the variables they use
+fn text(text: &Text, variables: &mut BTreeSet<String>) -> HclValue {
+    variables.extend(text.variables().into_iter().map(str::to_string));
+    match text {
+        Text::Literal(s) => HclValue::str(s),
+        Text::Template(parts) => HclValue::raw(hcl::template(parts)),
+    }
+}
+
+/// Autopilot languages are `en-US`, Lex locales are `en_US`
+fn lex_locale(language: &str) -> String {
+    language.replace('-', "_")
+}
+
+fn plain_text_message_group(text: HclValue) -> HclBlock {
+    HclBlock::new("message_group").block(
+        HclBlock::new("message").block(HclBlock::new("plain_text_message").attr("value", text)),
+    )
+}
+
+/// Attributes shared by every resource that lives in a bot locale
+fn in_locale(block: HclBlock, bot: &str) -> HclBlock {
+    block
+        .attr("bot_id", HclValue::raw(format!("{}.{}.id", LEX_BOT, bot)))
+        .attr(
+            "bot_version",
+            HclValue::raw(format!("{}.{}.bot_version", LEX_BOT_LOCALE, bot)),
+        )
+        .attr(
+            "locale_id",
+            HclValue::raw(format!("{}.{}.locale_id", LEX_BOT_LOCALE, bot)),
+        )
+}
+
+/// Where the conversation goes once the task is done: its first `redirect` action, or the
+/// `on_complete` of a `collect` before it
+fn next_redirect(task: &Task) -> Option<&Redirect> {
+    task.actions.iter().find_map(|action| match action {
+        Action::Redirect(redirect) => Some(redirect),
+        Action::Collect(collect) => collect.on_complete.as_ref(),
+        _ => None,
+    })
+}
+
+/// What the task remembers before it's done, as session attributes, which are strings. Later
+/// values of a key override the earlier ones, like in Autopilot's memory.
+fn remembered(task: &Task, variables: &mut BTreeSet<String>) -> Vec<(String, Expression)> {
+    let mut remembered: Vec<(String, Expression)> = vec![];
+    for action in &task.actions {
+        match action {
+            Action::Remember(memory) => {
+                for (key, value) in memory {
+                    value.walk_templates(&mut |parts| {
+                        variables.extend(
+                            parts
+                                .iter()
+                                .flat_map(TemplatePart::variables)
+                                .map(str::to_string),
+                        )
+                    });
+                    let value = match value {
+                        JsonValue::Str(s) => Expression::String(s.clone()),
+                        JsonValue::Template(parts) => Expression::Template(parts.clone()),
+                        // The code hook parses them back
+                        value => Expression::Json(value.clone()),
+                    };
+                    remembered.retain(|(k, _)| k != key);
+                    remembered.push((key.clone(), value));
+                }
+            }
+            Action::Redirect(_) => break,
+            Action::Collect(collect) if collect.on_complete.is_some() => break,
+            _ => {}
+        }
+    }
+    remembered
+}
+
+/// Where the code hook of the intent forwards to, if its task calls URLs
+fn code_hook_route(task: &Task, variables: &mut BTreeSet<String>) -> Option<Route> {
+    let endpoint = |url: &Text, method: &Option<String>| Endpoint {
+        url: url.clone(),
+        method: method.clone(),
+    };
+    let redirect = match next_redirect(task) {
+        Some(Redirect::Url { uri, method }) => Some(endpoint(uri, method)),
+        _ => None,
+    };
+    let webhooks: Vec<(String, Endpoint)> = collect_questions(task)
+        .into_iter()
+        .filter_map(|question| {
+            let webhook = question.validate.as_ref()?.webhook.as_ref()?;
+            Some((
+                question.name.clone(),
+                endpoint(&webhook.url, &webhook.method),
+            ))
+        })
+        .collect();
+    if redirect.is_none() && webhooks.is_empty() {
+        return None;
+    }
+
+    let urls = redirect
+        .iter()
+        .chain(webhooks.iter().map(|(_, webhook)| webhook));
+    variables.extend(urls.flat_map(|e| e.url.variables()).map(str::to_string));
+    Some(Route {
+        intent: task.unique_name.clone(),
+        redirect,
+        webhooks,
+        remember: vec![],
+    })
+}
+
+/// A dialog step of `kind` starting the intent of the task a `task://` redirect of `from` points
+/// to, if the assistant has such a task. Intents are referred to by name, since they may redirect
+/// to each other.
+fn start_intent(
+    kind: &str,
+    from: &Task,
+    unique_name: &str,
+    tasks: &[&Task],
+    warnings: &mut Vec<String>,
+) -> Option<HclBlock> {
+    let to = match tasks.iter().find(|t| t.unique_name == unique_name) {
+        Some(to) => to,
+        None => {
+            warnings.push(format!(
+                "{} redirects to task://{}, which is not a task of its assistant",
+                without_keys(&from.address),
+                unique_name
+            ));
+            return None;
+        }
+    };
+    Some(
+        HclBlock::new(kind)
+            .block(HclBlock::new("dialog_action").attr("type", HclValue::str("StartIntent")))
+            .block(HclBlock::new("intent").attr("name", HclValue::str(&to.unique_name))),
+    )
+}
+
+/// The task a `task://unique_name` URI points to
+fn task_of<'a>(uri: &str, tasks: &[&'a Task]) -> Option<&'a Task> {
+    let unique_name = uri.strip_prefix("task://")?;
+    tasks.iter().find(|t| t.unique_name == unique_name).copied()
+}
+
+fn generate_assistant(assistant: &Assistant, model: &Model, config: &mut LexConfig) {
+    let bot = assistant.name.as_str();
+    let tasks: Vec<&Task> = model
+        .tasks
+        .iter()
+        .filter(|t| t.assistant == assistant.name)
+        .collect();
+    let samples = || {
+        model
+            .task_samples
+            .iter()
+            .filter(|s| s.assistant == assistant.name)
+    };
+    let field_types: Vec<&FieldType> = model
+        .field_types
+        .iter()
+        .filter(|f| f.assistant == assistant.name)
+        .collect();
+    let field_values = || {
+        model
+            .field_values
+            .iter()
+            .filter(|v| v.assistant == assistant.name)
+    };
+
+    let mut bot_block = HclBlock::resource(LEX_BOT, bot)
+        .comments(&assistant.comments)
+        .attr("name", HclValue::str(&assistant.unique_name))
+        .attr("role_arn", HclValue::raw("var.lex_role_arn"))
+        .attr("idle_session_ttl_in_seconds", HclValue::Number(300.0));
+    if let Some(friendly_name) = &assistant.friendly_name {
+        bot_block = bot_block.attr("description", text(friendly_name, &mut config.variables));
+    }
+    config.blocks.push(
+        bot_block
+            .block(HclBlock::new("data_privacy").attr("child_directed", HclValue::Boolean(false))),
+    );
+    if assistant.log_queries {
+        config.warnings.push(format!(
+            "{} logs its queries, Lex conversation logs have to be set up on a bot alias",
+            without_keys(&assistant.address)
+        ));
+    }
+
+    let locale = samples()
+        .filter_map(|s| s.language.as_deref())
+        .chain(field_values().filter_map(|v| v.language.as_deref()))
+        .next()
+        .map(lex_locale)
+        .unwrap_or_else(|| DEFAULT_LOCALE.to_string());
+
+    let mut locale_block = HclBlock::resource(LEX_BOT_LOCALE, bot)
+        .attr("bot_id", HclValue::raw(format!("{}.{}.id", LEX_BOT, bot)))
+        .attr("bot_version", HclValue::str("DRAFT"))
+        .attr("locale_id", HclValue::str(locale))
+        .attr("n_lu_intent_confidence_threshold", HclValue::Number(0.4));
+    if let Some(voice) = &assistant.style_sheet.voice {
+        locale_block = locale_block
+            .block(HclBlock::new("voice_settings").attr("voice_id", HclValue::str(voice)));
+    }
+    config.blocks.push(locale_block);
+
+    for field_type in &field_types {
+        config
+            .blocks
+            .push(generate_slot_type(bot, field_type, model));
+    }
+    let mut custom_built_ins: Vec<&CustomBuiltIn> = vec![];
+    for question in tasks.iter().flat_map(|t| collect_questions(t)) {
+        let field_type = question.field_type.as_deref().unwrap_or("");
+        if let Some(custom) = built_ins::custom_built_in(field_type) {
+            if !custom_built_ins.iter().any(|c| c.name == custom.name) {
+                custom_built_ins.push(custom);
+            }
+        }
+    }
+    for custom in custom_built_ins {
+        config.summary.push(format!(
+            "{} has no Amazon equivalent, the questions of {} using it get the custom slot type {}",
+            custom.field_type, assistant.address, custom.name
+        ));
+        let values = custom.values.iter().map(|(value, synonyms)| {
+            (
+                value.to_string(),
+                synonyms.iter().map(|s| s.to_string()).collect(),
+            )
+        });
+        config.blocks.push(slot_type(
+            bot,
+            HclBlock::resource(LEX_SLOT_TYPE, &custom_built_in_name(bot, custom)),
+            custom.name,
+            values.collect(),
+        ));
+    }
+
+    let defaults = &assistant.defaults;
+    let fallback = defaults
+        .fallback
+        .as_deref()
+        .and_then(|f| task_of(f, &tasks));
+    if let Some(initiation) = defaults
+        .assistant_initiation
+        .as_deref()
+        .and_then(|i| task_of(i, &tasks))
+    {
+        config.warnings.push(format!(
+            "{} starts the conversations of {}, Lex bots wait for the user to speak first",
+            without_keys(&initiation.address),
+            without_keys(&assistant.address)
+        ));
+    }
+    if let Some(on_failure) = &defaults.collect_validate_on_failure {
+        config.warnings.push(format!(
+            "{} redirects to {} when a question fails validation, which is not carried over yet",
+            without_keys(&assistant.address),
+            on_failure
+        ));
+    }
+
+    let first_block = config.blocks.len();
+    let mut routes = vec![];
+    for task in &tasks {
+        // Lex rejects an intent with the same utterance twice, which separate samples may give
+        let mut utterances: Vec<&String> = vec![];
+        for sample in samples().filter(|s| s.task == task.name) {
+            if !utterances.contains(&&sample.tagged_text) {
+                utterances.push(&sample.tagged_text);
+            }
+        }
+
+        for key in ignored_keys(task) {
+            config.summary.push(format!(
+                "{} of {} has no Lex equivalent and was ignored",
+                key, task.address
+            ));
+        }
+
+        let is_fallback = fallback.map(|f| f.name == task.name).unwrap_or(false);
+        let mut comments = task.comments.clone();
+        if utterances.is_empty() && !is_fallback {
+            comments.push(
+                "No samples, the intent is only reachable through dialog transitions".to_string(),
+            );
+        }
+
+        let mut intent = in_locale(
+            HclBlock::resource(LEX_INTENT, &task.name).comments(&comments),
+            bot,
+        )
+        .attr("name", HclValue::str(&task.unique_name));
+        // Autopilot's fallback task is Lex's built-in fallback intent
+        if is_fallback {
+            intent = intent.attr(
+                "parent_intent_signature",
+                HclValue::str("AMAZON.FallbackIntent"),
+            );
+        }
+        intent = intent.blocks(
+            utterances
+                .into_iter()
+                .map(|u| HclBlock::new("sample_utterance").attr("utterance", HclValue::str(u))),
+        );
+        let remembered = remembered(task, &mut config.variables);
+        let intent_address = format!("{}.{}", LEX_INTENT, task.name);
+        let mut route = code_hook_route(task, &mut config.variables);
+        // Once its slots are filled the intent closes, and the conversation moves on from there
+        let next_step = match next_redirect(task) {
+            Some(Redirect::Task(unique_name)) => {
+                start_intent("next_step", task, unique_name, &tasks, &mut config.warnings)
+            }
+            _ => None,
+        };
+        // The memory is kept by the code hook when it fulfills the intent, and else when it closes
+        if let Some(route) = route.as_mut().filter(|r| r.redirect.is_some()) {
+            for (key, _) in &remembered {
+                config
+                    .memory_keys
+                    .entry(key.clone())
+                    .or_default()
+                    .push(format!("the code hook of {}", intent_address));
+            }
+            route.remember = remembered;
+        } else if next_step.is_some() || !remembered.is_empty() {
+            let mut next_step = next_step.unwrap_or_else(|| {
+                HclBlock::new("next_step").block(
+                    HclBlock::new("dialog_action").attr("type", HclValue::str("ElicitIntent")),
+                )
+            });
+            if !remembered.is_empty() {
+                for (key, _) in &remembered {
+                    config
+                        .memory_keys
+                        .entry(key.clone())
+                        .or_default()
+                        .push(format!("the closing of {}", intent_address));
+                }
+                let session_attributes = remembered
+                    .into_iter()
+                    .map(|(key, value)| (Expression::String(key), value))
+                    .collect();
+                next_step.body.insert(
+                    0,
+                    HclBodyItem::Attribute(
+                        "session_attributes".to_string(),
+                        HclValue::raw(hcl::expression(&Expression::Object(session_attributes))),
+                    ),
+                );
+            }
+            intent = intent.block(
+                HclBlock::new("closing_setting")
+                    .attr("active", HclValue::Boolean(true))
+                    .block(next_step),
+            );
+        }
+        // Lex can't call URLs, the code hooks of the intent forward to them instead
+        if let Some(route) = route {
+            if route.redirect.is_some() {
+                intent = intent.block(
+                    HclBlock::new("fulfillment_code_hook").attr("enabled", HclValue::Boolean(true)),
+                );
+            }
+            if !route.webhooks.is_empty() {
+                intent = intent.block(
+                    HclBlock::new("dialog_code_hook").attr("enabled", HclValue::Boolean(true)),
+                );
+            }
+            routes.push(route);
+        }
+        let questions = collect_questions(task);
+        if !questions.is_empty() {
+            // Set by the priorities of its slots below
+            intent = intent.block(
+                HclBlock::new("lifecycle").attr("ignore_changes", HclValue::raw("[slot_priority]")),
+            );
+        }
+        config.blocks.push(intent);
+
+        for question in &questions {
+            let slot = generate_slot(bot, task, question, &tasks, &field_types, config);
+            config.blocks.push(slot);
+        }
+        if !questions.is_empty() {
+            config
+                .blocks
+                .push(generate_slot_priorities(bot, task, &questions));
+        }
+    }
+
+    if tasks.iter().any(|t| !collect_questions(t).is_empty()) {
+        config.summary.push(format!(
+            "the slot priorities of the intents of {} are set with the AWS CLI and jq, which `terraform apply` needs",
+            assistant.address
+        ));
+    }
+
+    if !routes.is_empty() {
+        let depends_on: Vec<String> = config.blocks[first_block..]
+            .iter()
+            .filter(|b| b.kind == "resource")
+            .map(|b| b.labels.join("."))
+            .collect();
+        config.blocks.extend(code_hook::generate(
+            bot,
+            &assistant.unique_name,
+            &routes,
+            &depends_on,
+        ));
+        if config.files.is_empty() {
+            config.files.push((
+                code_hook::HANDLER_FILE.to_string(),
+                code_hook::HANDLER.to_string(),
+            ));
+        }
+    }
+
+    for sample in samples() {
+        if !tasks.iter().any(|t| t.name == sample.task) {
+            config.warnings.push(format!(
+                "{} does not reference a task of {}, its utterances are dropped",
+                without_keys(&sample.address),
+                without_keys(&assistant.address)
+            ));
+        }
+    }
+}
+
+fn generate_slot_type(bot: &str, field_type: &FieldType, model: &Model) -> HclBlock {
+    let field_values = || {
+        model
+            .field_values
+            .iter()
+            .filter(|v| v.field_type == field_type.name)
+    };
+
+    // Values in declaration order, each with the synonyms pointing at it
+    let mut values: Vec<(String, Vec<String>)> = vec![];
+    for field_value in field_values().filter(|v| v.synonym_of.is_none()) {
+        if !values.iter().any(|(v, _)| *v == field_value.value) {
+            values.push((field_value.value.clone(), vec![]));
+        }
+    }
+    // `Model::check_synonyms` made sure they all point at a value
+    for field_value in field_values() {
+        let synonym_of = field_value.synonym_of.as_ref();
+        if let Some((_, synonyms)) = values.iter_mut().find(|(v, _)| Some(v) == synonym_of) {
+            if !synonyms.contains(&field_value.value) {
+                synonyms.push(field_value.value.clone());
+            }
+        }
+    }
+
+    slot_type(
+        bot,
+        HclBlock::resource(LEX_SLOT_TYPE, &field_type.name).comments(&field_type.comments),
+        &field_type.unique_name,
+        values,
+    )
+}
+
+/// The resource name of the slot type a bot gets for a custom built-in
+fn custom_built_in_name(bot: &str, custom: &CustomBuiltIn) -> String {
+    format!("{}_{}", bot, custom.name)
+}
+
+/// A slot type of the bot, with its values and their synonyms
+fn slot_type(
+    bot: &str,
+    resource: HclBlock,
+    name: &str,
+    values: Vec<(String, Vec<String>)>,
+) -> HclBlock {
+    in_locale(resource, bot)
+        .attr("name", HclValue::str(name))
+        .block(
+            HclBlock::new("value_selection_setting")
+                .attr("resolution_strategy", HclValue::str("TopResolution")),
+        )
+        .blocks(values.into_iter().map(|(value, synonyms)| {
+            HclBlock::new("slot_type_values")
+                .block(HclBlock::new("sample_value").attr("value", HclValue::Str(value)))
+                .blocks(
+                    synonyms
+                        .into_iter()
+                        .map(|s| HclBlock::new("synonyms").attr("value", HclValue::Str(s))),
+                )
+        }))
+}
+
+/// Lex resources are named after the Autopilot ones, see `autopilot::name_of`, so the moves of
+/// an assistant, a task or a field type that change its name are carried over, moved along with
+/// their module or on their own
+fn generate_moved(model: &Model, moves: &Moves) -> Vec<HclBlock> {
+    let moved_block = |res_type: &str, from: &str, to: &str| {
+        HclBlock::new("moved")
+            .attr("from", HclValue::raw(format!("{}.{}", res_type, from)))
+            .attr("to", HclValue::raw(format!("{}.{}", res_type, to)))
+    };
+
+    let mut blocks = vec![];
+    for m in &moves.0 {
+        // The names of what the move renamed, found where it is declared now
+        let current = moves.current(&m.to);
+        let renamed = |address: &str| {
+            if !moved::contains(&current, address) {
+                return None;
+            }
+            let rest = &address[current.len()..];
+            let from = name_of(&format!("{}{}", m.from, rest))?;
+            let to = name_of(&format!("{}{}", m.to, rest))?;
+            (from != to).then_some((from, to))
+        };
+
+        for assistant in &model.assistants {
+            if let Some((from, to)) = renamed(&assistant.address) {
+                blocks.push(moved_block(LEX_BOT, &from, &to));
+                blocks.push(moved_block(LEX_BOT_LOCALE, &from, &to));
+            }
+        }
+        for task in &model.tasks {
+            if let Some((from, to)) = renamed(&task.address) {
+                blocks.push(moved_block(LEX_INTENT, &from, &to));
+                let questions = collect_questions(task);
+                for question in &questions {
+                    blocks.push(moved_block(
+                        LEX_SLOT,
+                        &format!("{}_{}", from, question.name),
+                        &format!("{}_{}", to, question.name),
+                    ));
+                }
+                if !questions.is_empty() {
+                    blocks.push(moved_block(
+                        TERRAFORM_DATA,
+                        &format!("{}_slot_priority", from),
+                        &format!("{}_slot_priority", to),
+                    ));
+                }
+            }
+        }
+        for field_type in &model.field_types {
+            if let Some((from, to)) = renamed(&field_type.address) {
+                blocks.push(moved_block(LEX_SLOT_TYPE, &from, &to));
+            }
+        }
+    }
+    blocks
+}
+
+/// The keys of the actions of the task that the conversion ignores
+fn ignored_keys(task: &Task) -> Vec<&String> {
+    let questions = collect_questions(task).into_iter().flat_map(|q| &q.ignored);
+    task.actions
+        .iter()
+        .flat_map(|action| match action {
+            Action::Listen(listen) => listen.ignored.as_slice(),
+            Action::Handoff(handoff) => handoff.ignored.as_slice(),
+            _ => &[],
+        })
+        .chain(questions)
+        .collect()
+}
+
+/// The questions of every `collect` action in the task
+fn collect_questions(task: &Task) -> Vec<&Question> {
+    task.actions
+        .iter()
+        .filter_map(|action| match action {
+            Action::Collect(collect) => Some(&collect.questions),
+            _ => None,
+        })
+        .flatten()
+        .collect()
+}
+
+/// The texts said one after the other, e.g. a failure message and the question again
+fn concatenated(first: &Text, second: &Text) -> Text {
+    let parts = |text: &Text| match text {
+        Text::Literal(s) => vec![TemplatePart::Literal(s.clone())],
+        Text::Template(parts) => parts.clone(),
+    };
+    match (first, second) {
+        (Text::Literal(first), Text::Literal(second)) => {
+            Text::Literal(format!("{} {}", first, second))
+        }
+        _ => Text::Template(
+            [
+                parts(first),
+                vec![TemplatePart::Literal(" ".to_string())],
+                parts(second),
+            ]
+            .concat(),
+        ),
+    }
+}
+
+/// A slot of the intent of the task, elicited with the question and then re-prompted with the
+/// failure messages in order
+fn generate_slot(
+    bot: &str,
+    task: &Task,
+    question: &Question,
+    tasks: &[&Task],
+    field_types: &[&FieldType],
+    config: &mut LexConfig,
+) -> HclBlock {
+    let name = question.name.as_str();
+    let field_type = question.field_type.as_deref().unwrap_or("");
+
+    let custom_field_type = field_types.iter().find(|ft| ft.unique_name == field_type);
+    let slot_type_id = if let Some(ft) = custom_field_type {
+        HclValue::raw(format!("{}.{}.slot_type_id", LEX_SLOT_TYPE, ft.name))
+    } else if let Some(amazon) = built_ins::amazon_slot_type(field_type) {
+        HclValue::str(amazon)
+    } else if let Some(custom) = built_ins::custom_built_in(field_type) {
+        HclValue::raw(format!(
+            "{}.{}.slot_type_id",
+            LEX_SLOT_TYPE,
+            custom_built_in_name(bot, custom)
+        ))
+    } else {
+        config.warnings.push(format!(
+            "question \"{}\" of {} uses the field type \"{}\", which has no Lex equivalent yet",
+            name,
+            without_keys(&task.address),
+            field_type
+        ));
+        HclValue::str("AMAZON.AlphaNumeric")
+    };
+
+    let validate = question.validate.as_ref();
+    // Autopilot counts the first attempt, Lex only the retries
+    let max_retries = match validate.and_then(|v| v.max_attempts.as_ref()) {
+        Some(max_attempts) => {
+            let retries = max_attempts.num_attempts.saturating_sub(1);
+            if retries > MAX_RETRIES {
+                config.warnings.push(format!(
+                    "question \"{}\" of {} allows {} attempts, Lex allows {} at most",
+                    name,
+                    without_keys(&task.address),
+                    max_attempts.num_attempts,
+                    MAX_RETRIES + 1
+                ));
+            }
+            retries.min(MAX_RETRIES)
+        }
+        None => DEFAULT_MAX_RETRIES,
+    };
+    let reprompts: Vec<Text> = match validate.and_then(|v| v.on_failure.as_ref()) {
+        Some(on_failure) => on_failure
+            .messages
+            .iter()
+            .take(max_retries.min(MAX_MESSAGE_GROUPS - 1) as usize)
+            .map(|message| match on_failure.repeat_question {
+                true => concatenated(message, &question.question),
+                false => message.clone(),
+            })
+            .collect(),
+        None => vec![],
+    };
+
+    let mut prompt = HclBlock::new("prompt_specification")
+        .attr("max_retries", HclValue::Number(max_retries as f64));
+    if !reprompts.is_empty() {
+        prompt = prompt.attr("message_selection_strategy", HclValue::str("Ordered"));
+    }
+    let messages = std::iter::once(&question.question).chain(&reprompts);
+    for message in messages {
+        prompt = prompt.block(plain_text_message_group(text(
+            message,
+            &mut config.variables,
+        )));
+    }
+
+    let slot = in_locale(
+        HclBlock::resource(LEX_SLOT, &format!("{}_{}", task.name, name)),
+        bot,
+    )
+    .attr(
+        "intent_id",
+        HclValue::raw(format!("{}.{}.intent_id", LEX_INTENT, task.name)),
+    )
+    .attr("name", HclValue::str(name))
+    .attr("slot_type_id", slot_type_id);
+    let mut elicitation = HclBlock::new("value_elicitation_setting")
+        .attr("slot_constraint", HclValue::str("Required"))
+        .block(prompt);
+    // Autopilot redirects once the attempts are exhausted, Lex once the retries are
+    let failure_redirect = validate
+        .and_then(|v| v.max_attempts.as_ref())
+        .and_then(|m| m.redirect.as_ref());
+    if let Some(Redirect::Url { uri, .. }) = failure_redirect {
+        config.warnings.push(format!(
+            "question \"{}\" of {} redirects to {} once its attempts are exhausted, which is not carried over",
+            name,
+            without_keys(&task.address),
+            match uri {
+                Text::Literal(uri) => uri.clone(),
+                Text::Template(parts) => hcl::template(parts),
+            }
+        ));
+    }
+    if let Some(Redirect::Task(unique_name)) = failure_redirect {
+        let failure_next_step = start_intent(
+            "failure_next_step",
+            task,
+            unique_name,
+            tasks,
+            &mut config.warnings,
+        );
+        if let Some(failure_next_step) = failure_next_step {
+            elicitation =
+                elicitation.block(HclBlock::new("slot_capture_setting").block(failure_next_step));
+        }
+    }
+    slot.block(elicitation)
+}
+
+/// Lex elicits the slots of an intent by the `slot_priority` of the intent, which can't hold
+/// them as the slots are created after their intent. So once they are, the priorities are set
+/// in question order by updating the intent with the AWS CLI, keeping the rest of it as is.
+fn generate_slot_priorities(bot: &str, task: &Task, questions: &[&Question]) -> HclBlock {
+    let priorities: Vec<String> = questions
+        .iter()
+        .enumerate()
+        .map(|(i, question)| {
+            format!(
+                "{{ priority = {}, slot_id = {}.{}_{}.slot_id }}",
+                i + 1,
+                LEX_SLOT,
+                task.name,
+                question.name
+            )
+        })
+        .collect();
+    let command = "aws lexv2-models update-intent --cli-input-json \"$(aws lexv2-models describe-intent \
+        --bot-id \"$bot_id\" --bot-version \"$bot_version\" --locale-id \"$locale_id\" --intent-id \"$intent_id\" \
+        | jq --argjson slots \"$slot_priority\" \
+        'del(.creationDateTime, .lastUpdatedDateTime) | .slotPriorities = [$slots[] | { priority, slotId: .slot_id }]')\"";
+
+    HclBlock::resource(TERRAFORM_DATA, &format!("{}_slot_priority", task.name))
+        .attr(
+            "triggers_replace",
+            HclValue::raw(format!("[{}]", priorities.join(", "))),
+        )
+        .block(
+            HclBlock::new("provisioner")
+                .label("local-exec")
+                .attr("command", HclValue::str(command))
+                .block(
+                    in_locale(HclBlock::new("environment"), bot)
+                        .attr(
+                            "intent_id",
+                            HclValue::raw(format!("{}.{}.intent_id", LEX_INTENT, task.name)),
+                        )
+                        .attr(
+                            "slot_priority",
+                            HclValue::raw("jsonencode(self.triggers_replace)"),
+                        ),
+                ),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ast::Expression,
+        expand::expand,
+        modules,
+        parser::{only_resources, parse_blocks, parse_file, Mode},
+        test_utils::directory,
+        variables,
+    };
+    use std::{collections::HashMap, path::Path};
+
+    fn generate_from(source: &str) -> LexConfig {
+        let parsed = parse_file("main.tf", source, Mode::Strict).expect("Failed parsing the data");
+        let moves = Moves::from_blocks(&parsed.blocks, &parsed.spans)
+            .expect("Failed reading the moved blocks");
+        let blocks = expand(parsed.blocks, &HashMap::new()).expect("Failed expanding the blocks");
+        let model =
+            Model::from_resources(&only_resources(blocks)).expect("Failed building the model");
+        generate(&model, &moves)
+    }
+
+    #[test]
+    fn generate_bot_and_locale() {
+        let config = generate_from(
+            "resource \"twilio_autopilot_assistants_v1\" \"survey\" {
+                unique_name   = \"demo_chatbot\"
+                friendly_name = \"A bot\"
+                style_sheet   = jsonencode({ \"style_sheet\": { \"voice\": { \"say_voice\": \"Polly.Joanna\" } } })
+            }",
+        );
+
+        let hcl = config.to_hcl();
+
+        assert!(hcl.contains("resource \"aws_lexv2models_bot\" \"survey\" {"));
+        assert!(hcl.contains("name                        = \"demo_chatbot\""));
+        assert!(hcl.contains("resource \"aws_lexv2models_bot_locale\" \"survey\" {"));
+        assert!(hcl.contains("voice_id = \"Joanna\""));
+        assert!(config.warnings.is_empty());
+    }
+
+    #[test]
+    fn summarize_ignored_keys() {
+        let config = generate_from(
+            r#"resource "twilio_autopilot_assistants_v1" "survey" {
+                unique_name = "demo_chatbot"
+            }
+
+            resource "twilio_autopilot_assistants_tasks_v1" "agent" {
+                unique_name   = "agent"
+                assistant_sid = twilio_autopilot_assistants_v1.survey.sid
+                actions = jsonencode({ "actions" : [
+                    { "listen" : { "voice_digits" : { "num_digits" : 1 } } },
+                    { "handoff" : { "channel" : "voice", "uri" : "https://example.com", "timeout" : 60 } }
+                ] })
+            }"#,
+        );
+
+        assert_eq!(
+            config.summary,
+            vec![
+                "actions[0].listen.voice_digits of twilio_autopilot_assistants_tasks_v1.agent has \
+                no Lex equivalent and was ignored",
+                "actions[1].handoff.timeout of twilio_autopilot_assistants_tasks_v1.agent has no \
+                Lex equivalent and was ignored",
+            ]
+        );
+    }
+
+    #[test]
+    fn generate_intent_with_utterances() {
+        let config = generate_from(
+            "resource \"twilio_autopilot_assistants_v1\" \"survey\" {
+                unique_name = \"demo_chatbot\"
+            }
+            resource \"twilio_autopilot_assistants_tasks_v1\" \"greeting\" {
+                unique_name   = \"greeting\"
+                assistant_sid = twilio_autopilot_assistants_v1.survey.sid
+            }
+            resource \"twilio_autopilot_assistants_tasks_samples_v1\" \"greeting_group\" {
+                for_each      = toset([\"hi\", \"hello\"])
+                assistant_sid = twilio_autopilot_assistants_v1.survey.sid
+                task_sid      = twilio_autopilot_assistants_tasks_v1.greeting.sid
+                language      = \"es-MX\"
+                tagged_text   = each.key
+            }",
+        );
+
+        let intent = config
+            .blocks
+            .iter()
+            .find(|b| b.labels == vec![LEX_INTENT, "greeting"])
+            .expect("The intent was not generated");
+
+        assert_eq!(
+            intent.to_string(),
+            "resource \"aws_lexv2models_intent\" \"greeting\" {
+  bot_id      = aws_lexv2models_bot.survey.id
+  bot_version = aws_lexv2models_bot_locale.survey.bot_version
+  locale_id   = aws_lexv2models_bot_locale.survey.locale_id
+  name        = \"greeting\"
+
+  sample_utterance {
+    utterance = \"hi\"
+  }
+
+  sample_utterance {
+    utterance = \"hello\"
+  }
+}
+"
+        );
+        assert!(config
+            .to_hcl()
+            .contains("locale_id                        = \"es_MX\""));
+    }
+
+    /// `main.tf` with its variables set, and with synthetic stand-ins for its modules, which live
+    /// in another repository
+    fn generate_main_with_stub_modules() -> LexConfig {
+        let source = std::fs::read_to_string("./main.tf").unwrap();
+        let parsed = parse_file("main.tf", &source, Mode::Strict).unwrap();
+        let values = [
+            ("gender_field_type", "default"),
+            ("serverless_url", "https://serverless.example.com"),
+            ("unknown_value", "Unknown"),
+        ]
+        .map(|(name, value)| (name.to_string(), Expression::String(value.to_string())));
+        let mut values = HashMap::from(values);
+        values.insert(
+            "unknown_synonyms".to_string(),
+            Expression::Tuple(vec![
+                Expression::String("prefer not to answer".to_string()),
+                Expression::String("X".to_string()),
+            ]),
+        );
+        let variables = variables::bind(&parsed.blocks, values).unwrap();
+        let blocks = expand(parsed.blocks, &variables).unwrap();
+        // main.tf calls the modules of a `terraform-modules` checkout next to it, which the ones
+        // of `tests/stub-modules/terraform-modules` stand in for
+        let dir = Path::new("tests/stub-modules/terraform-modules");
+        let inlined = modules::inline(blocks, dir, &variables, Mode::Strict).unwrap();
+        let model = Model::from_resources(&only_resources(inlined.blocks)).unwrap();
+        generate(&model, &Moves::default())
+    }
+
+    #[test]
+    fn name_lex_resources_after_modules() {
+        let dir = directory(
+            "lex-modules",
+            &[(
+                "modules/age/main.tf",
+                r#"
+variable "name" {}
+
+resource "twilio_autopilot_assistants_field_types_v1" "age" {
+  assistant_sid = twilio_autopilot_assistants_v1.survey.sid
+  unique_name   = var.name
+}
+"#,
+            )],
+        );
+        let (_, blocks) = parse_blocks(
+            r#"
+resource "twilio_autopilot_assistants_v1" "survey" {
+  unique_name = "survey"
+}
+
+module "age" {
+  count  = 2
+  source = "./modules/age"
+  name   = "Age${count.index}"
+}
+"#,
+        )
+        .unwrap();
+        let blocks = expand(blocks, &HashMap::new()).unwrap();
+        let inlined = modules::inline(blocks, &dir, &HashMap::new(), Mode::Strict).unwrap();
+        let model = Model::from_resources(&only_resources(inlined.blocks)).unwrap();
+        let config = generate(&model, &Moves::default());
+
+        let hcl = config.to_hcl();
+        assert!(hcl.contains("resource \"aws_lexv2models_slot_type\" \"age_0_age\" {"));
+        assert!(hcl.contains("resource \"aws_lexv2models_slot_type\" \"age_1_age\" {"));
+        assert!(config.warnings.is_empty());
+    }
+
+    /// Compares the blocks of `res_type` to a file of `golden/`, which `UPDATE_GOLDEN=1` rewrites
+    fn assert_golden(config: &LexConfig, res_type: &str, golden: &str) {
+        let blocks: Vec<HclBlock> = config
+            .blocks
+            .iter()
+            .filter(|b| b.labels.first().map(String::as_str) == Some(res_type))
+            .cloned()
+            .collect();
+        let generated = hcl::render(&blocks);
+
+        let path = Path::new("golden").join(golden);
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::write(&path, &generated).unwrap();
+        }
+        let expected = std::fs::read_to_string(&path).unwrap();
+        assert!(
+            generated == expected,
+            "{} differs from the generated blocks, run with UPDATE_GOLDEN=1 to update it:\n{}",
+            path.display(),
+            generated
+        );
+    }
+
+    #[test]
+    fn generate_the_slot_types_of_the_development_pre_survey() {
+        let source = std::fs::read_to_string("./development_pre_survey.tf").unwrap();
+        let config = generate_from(&source);
+
+        assert_golden(
+            &config,
+            LEX_SLOT_TYPE,
+            "development_pre_survey.slot_types.tf",
+        );
+    }
+
+    #[test]
+    fn generate_the_intents_of_main_with_stub_modules() {
+        let config = generate_main_with_stub_modules();
+
+        assert_golden(&config, LEX_INTENT, "main_with_stub_modules.intents.tf");
+    }
+
+    #[test]
+    fn generate_slot_type_with_synonyms() {
+        let config = generate_from(
+            "resource \"twilio_autopilot_assistants_v1\" \"survey\" {
+                unique_name = \"demo_chatbot\"
+            }
+            resource \"twilio_autopilot_assistants_field_types_v1\" \"gender\" {
+                unique_name   = \"Gender\"
+                assistant_sid = twilio_autopilot_assistants_v1.survey.sid
+            }
+            resource \"twilio_autopilot_assistants_field_types_field_values_v1\" \"gender_group\" {
+                for_each       = toset([\"Boy\", \"Girl\"])
+                assistant_sid  = twilio_autopilot_assistants_v1.survey.sid
+                field_type_sid = twilio_autopilot_assistants_field_types_v1.gender.sid
+                value          = each.key
+            }
+            resource \"twilio_autopilot_assistants_field_types_field_values_v1\" \"boy_group\" {
+                for_each       = toset([\"male\"])
+                assistant_sid  = twilio_autopilot_assistants_v1.survey.sid
+                field_type_sid = twilio_autopilot_assistants_field_types_v1.gender.sid
+                synonym_of     = \"Boy\"
+                value          = each.key
+            }",
+        );
+
+        let hcl = config.to_hcl();
+
+        assert!(hcl.contains(
+            "  slot_type_values {
+    sample_value {
+      value = \"Boy\"
+    }
+
+    synonyms {
+      value = \"male\"
+    }
+  }
+
+  slot_type_values {
+    sample_value {
+      value = \"Girl\"
+    }
+  }
+"
+        ));
+    }
+
+    #[test]
+    fn generate_interpolated_prompt() {
+        let config = generate_from(
+            "resource \"twilio_autopilot_assistants_v1\" \"survey\" {
+                unique_name = \"demo_chatbot\"
+            }
+            resource \"twilio_autopilot_assistants_tasks_v1\" \"survey\" {
+                unique_name   = \"survey\"
+                assistant_sid = twilio_autopilot_assistants_v1.survey.sid
+                actions       = jsonencode({
+                    \"actions\" : [{ \"collect\" : { \"name\" : \"survey\", \"questions\" : [
+                        { \"name\" : \"age\", \"type\" : \"Twilio.NUMBER\", \"question\" : \"Welcome to ${var.helpline}!\" }
+                    ] } }]
+                })
+            }",
+        );
+
+        let hcl = config.to_hcl();
+
+        assert!(hcl.contains("variable \"helpline\" {\n  type = string\n}"));
+        assert!(hcl.contains("value = \"Welcome to ${var.helpline}!\""));
+    }
+
+    #[test]
+    fn generate_slots_with_reprompts() {
+        let config = generate_from(
+            "resource \"twilio_autopilot_assistants_v1\" \"survey\" {
+                unique_name = \"demo_chatbot\"
+            }
+            resource \"twilio_autopilot_assistants_tasks_v1\" \"survey\" {
+                unique_name   = \"survey\"
+                assistant_sid = twilio_autopilot_assistants_v1.survey.sid
+                actions       = jsonencode({
+                    \"actions\" : [{ \"collect\" : { \"name\" : \"survey\", \"questions\" : [
+                        { \"name\" : \"age\", \"type\" : \"Twilio.NUMBER\", \"question\" : \"How old are you?\",
+                          \"validate\" : {
+                            \"on_failure\" : { \"messages\" : [{ \"say\" : \"Sorry.\" }, { \"say\" : \"Sorry again.\" }], \"repeat_question\" : true },
+                            \"max_attempts\" : { \"num_attempts\" : 2, \"redirect\" : \"task://counselor_handoff\" }
+                          } },
+                        { \"name\" : \"city\", \"type\" : \"Twilio.CITY\", \"question\" : \"Where do you live?\" }
+                    ] } }]
+                })
+            }",
+        );
+
+        let slots: Vec<String> = config
+            .blocks
+            .iter()
+            .filter(|b| b.labels.first().map(String::as_str) == Some(LEX_SLOT))
+            .map(|b| b.to_string())
+            .collect();
+
+        // One retry, so only the first failure message is said
+        assert!(slots[0].contains("slot_type_id = \"AMAZON.Number\""));
+        assert!(slots[0].contains("max_retries                = 1"));
+        assert!(slots[0].contains("message_selection_strategy = \"Ordered\""));
+        assert!(slots[0].contains("value = \"Sorry. How old are you?\""));
+        assert!(!slots[0].contains("Sorry again."));
+        assert!(slots[1].contains("max_retries = 2"));
+
+        let priorities = config
+            .blocks
+            .iter()
+            .find(|b| b.labels == [TERRAFORM_DATA, "survey_slot_priority"])
+            .unwrap()
+            .to_string();
+        assert!(priorities.contains(
+            "triggers_replace = [{ priority = 1, slot_id = aws_lexv2models_slot.survey_age.slot_id }, { priority = 2, slot_id = aws_lexv2models_slot.survey_city.slot_id }]"
+        ));
+        assert!(priorities.contains("intent_id     = aws_lexv2models_intent.survey.intent_id"));
+        let intent = config
+            .blocks
+            .iter()
+            .find(|b| b.labels == [LEX_INTENT, "survey"])
+            .unwrap();
+        assert!(intent
+            .to_string()
+            .contains("ignore_changes = [slot_priority]"));
+    }
+
+    #[test]
+    fn generate_task_redirects() {
+        let config = generate_from(
+            "resource \"twilio_autopilot_assistants_v1\" \"survey\" {
+                unique_name = \"demo_chatbot\"
+            }
+            resource \"twilio_autopilot_assistants_tasks_v1\" \"greeting\" {
+                unique_name   = \"greeting\"
+                assistant_sid = twilio_autopilot_assistants_v1.survey.sid
+                actions       = jsonencode({ \"actions\" : [{ \"say\" : \"Hi!\" }, { \"redirect\" : \"task://survey\" }] })
+            }
+            resource \"twilio_autopilot_assistants_tasks_v1\" \"survey\" {
+                unique_name   = \"survey\"
+                assistant_sid = twilio_autopilot_assistants_v1.survey.sid
+                actions       = jsonencode({
+                    \"actions\" : [{ \"collect\" : { \"name\" : \"survey\", \"on_complete\" : { \"redirect\" : \"task://goodbye\" }, \"questions\" : [
+                        { \"name\" : \"age\", \"type\" : \"Twilio.NUMBER\", \"question\" : \"How old are you?\",
+                          \"validate\" : { \"max_attempts\" : { \"num_attempts\" : 2, \"redirect\" : \"task://greeting\" } } }
+                    ] } }]
+                })
+            }",
+        );
+
+        let hcl = config.to_hcl();
+
+        assert!(hcl.contains(
+            "  closing_setting {
+    active = true
+
+    next_step {
+      dialog_action {
+        type = \"StartIntent\"
+      }
+
+      intent {
+        name = \"survey\"
+      }
+    }
+  }
+"
+        ));
+        assert!(hcl.contains(
+            "    slot_capture_setting {
+      failure_next_step {
+        dialog_action {
+          type = \"StartIntent\"
+        }
+
+        intent {
+          name = \"greeting\"
+        }
+      }
+    }
+"
+        ));
+        assert_eq!(
+            config.warnings,
+            vec!["twilio_autopilot_assistants_tasks_v1.survey redirects to task://goodbye, which is \
+                not a task of its assistant"]
+        );
+    }
+
+    #[test]
+    fn generate_the_code_hook_of_main_with_stub_modules() {
+        let config = generate_main_with_stub_modules();
+
+        assert_golden(
+            &config,
+            "aws_lambda_function",
+            "main_with_stub_modules.code_hook.tf",
+        );
+        assert!(config
+            .to_hcl()
+            .contains("source_arn    = awscc_lex_bot_alias.pre_survey.arn"));
+        assert_eq!(config.files.len(), 1);
+        assert_eq!(config.files[0].0, "lex_code_hook.js");
+    }
+
+    #[test]
+    fn generate_webhooks_validating_questions() {
+        let config = generate_from(
+            "resource \"twilio_autopilot_assistants_v1\" \"survey\" {
+                unique_name = \"demo_chatbot\"
+            }
+            resource \"twilio_autopilot_assistants_tasks_v1\" \"survey\" {
+                unique_name   = \"survey\"
+                assistant_sid = twilio_autopilot_assistants_v1.survey.sid
+                actions       = jsonencode({
+                    \"actions\" : [{ \"collect\" : { \"name\" : \"survey\", \"questions\" : [
+                        { \"name\" : \"age\", \"type\" : \"Twilio.NUMBER\", \"question\" : \"How old are you?\",
+                          \"validate\" : { \"webhook\" : { \"url\" : \"${var.url}/validate\", \"method\" : \"GET\" } } }
+                    ] } }]
+                })
+            }",
+        );
+
+        let hcl = config.to_hcl();
+
+        assert!(hcl.contains("  dialog_code_hook {\n    enabled = true\n  }"));
+        assert!(!hcl.contains("fulfillment_code_hook"));
+        assert!(hcl.contains(
+            "ROUTES = jsonencode({ survey = { webhooks = { age = { url = \"${var.url}/validate\", method = \"GET\" } } } })"
+        ));
+        assert!(hcl.contains("variable \"url\" {"));
+        assert!(hcl.contains("variable \"lambda_role_arn\" {"));
+    }
+
+    #[test]
+    fn generate_remembered_session_attributes() {
+        let config = generate_from(
+            "resource \"twilio_autopilot_assistants_v1\" \"survey\" {
+                unique_name = \"demo_chatbot\"
+            }
+            resource \"twilio_autopilot_assistants_tasks_v1\" \"handoff\" {
+                unique_name   = \"handoff\"
+                assistant_sid = twilio_autopilot_assistants_v1.survey.sid
+                actions       = jsonencode({ \"actions\" : [
+                    { \"remember\" : { \"sendToAgent\" : false, \"at\" : \"start\" } },
+                    { \"remember\" : { \"sendToAgent\" : true } }
+                ] })
+            }
+            resource \"twilio_autopilot_assistants_tasks_v1\" \"redirect\" {
+                unique_name   = \"redirect\"
+                assistant_sid = twilio_autopilot_assistants_v1.survey.sid
+                actions       = jsonencode({ \"actions\" : [
+                    { \"remember\" : { \"at\" : \"${var.stage}\" } },
+                    { \"redirect\" : \"https://example.com/redirect\" },
+                    { \"remember\" : { \"never\" : \"reached\" } }
+                ] })
+            }",
+        );
+
+        let hcl = config.to_hcl();
+
+        assert!(
+            hcl.contains("session_attributes = { at = \"start\", sendToAgent = jsonencode(true) }")
+        );
+        assert!(hcl.contains(
+            "ROUTES = jsonencode({ redirect = { url = \"https://example.com/redirect\", remember = { at = \"${var.stage}\" } } })"
+        ));
+        assert!(hcl.contains("variable \"stage\" {"));
+        assert_eq!(
+            config.memory_keys,
+            BTreeMap::from([
+                (
+                    "at".to_string(),
+                    vec![
+                        "the closing of aws_lexv2models_intent.handoff".to_string(),
+                        "the code hook of aws_lexv2models_intent.redirect".to_string()
+                    ]
+                ),
+                (
+                    "sendToAgent".to_string(),
+                    vec!["the closing of aws_lexv2models_intent.handoff".to_string()]
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn generate_the_slots_of_the_development_pre_survey() {
+        let source = std::fs::read_to_string("./development_pre_survey.tf").unwrap();
+        let config = generate_from(&source);
+
+        assert_golden(&config, LEX_SLOT, "development_pre_survey.slots.tf");
+        assert_golden(
+            &config,
+            TERRAFORM_DATA,
+            "development_pre_survey.slot_priorities.tf",
+        );
+    }
+
+    #[test]
+    fn generate_moved_blocks() {
+        let config = generate_from(
+            "moved {
+                from = twilio_autopilot_assistants_tasks_v1.survey
+                to   = twilio_autopilot_assistants_tasks_v1.pre_survey
+            }
+            moved {
+                from = twilio_autopilot_assistants_v1.survey
+                to   = twilio_autopilot_assistants_v1.other
+            }
+            resource \"twilio_autopilot_assistants_v1\" \"survey\" {
+                unique_name = \"demo_chatbot\"
+            }
+            resource \"twilio_autopilot_assistants_tasks_v1\" \"pre_survey\" {
+                unique_name   = \"survey\"
+                assistant_sid = twilio_autopilot_assistants_v1.survey.sid
+                actions       = jsonencode({
+                    \"actions\" : [{ \"collect\" : { \"name\" : \"survey\", \"questions\" : [
+                        { \"name\" : \"age\", \"type\" : \"Twilio.NUMBER\", \"question\" : \"How old are you?\" }
+                    ] } }]
+                })
+            }",
+        );
+
+        let moved: Vec<String> = config
+            .blocks
+            .iter()
+            .filter(|b| b.kind == "moved")
+            .map(|b| b.to_string())
+            .collect();
+
+        // The assistant isn't moved to a declared one, so the bot stays as it is
+        assert_eq!(
+            moved,
+            vec![
+                "moved {
+  from = aws_lexv2models_intent.survey
+  to   = aws_lexv2models_intent.pre_survey
+}
+",
+                "moved {
+  from = aws_lexv2models_slot.survey_age
+  to   = aws_lexv2models_slot.pre_survey_age
+}
+",
+                "moved {
+  from = terraform_data.survey_slot_priority
+  to   = terraform_data.pre_survey_slot_priority
+}
+",
+            ]
+        );
+    }
+
+    #[test]
+    fn generate_moved_blocks_of_modules() {
+        let dir = directory(
+            "lex-moved-modules",
+            &[(
+                "modules/age/main.tf",
+                r#"
+resource "twilio_autopilot_assistants_field_types_v1" "age" {
+  assistant_sid = twilio_autopilot_assistants_v1.survey.sid
+  unique_name   = "Age"
+}
+"#,
+            )],
+        );
+        let parsed = parse_file(
+            "main.tf",
+            r#"
+resource "twilio_autopilot_assistants_v1" "survey" {
+  unique_name = "survey"
+}
+
+module "age_field" {
+  count  = 1
+  source = "./modules/age"
+}
+
+moved {
+  from = twilio_autopilot_assistants_field_types_v1.age
+  to   = module.default_age_field[0].twilio_autopilot_assistants_field_types_v1.age
+}
+
+moved {
+  from = module.default_age_field
+  to   = module.age_field
+}
+"#,
+            Mode::Strict,
+        )
+        .unwrap();
+        let moves = Moves::from_blocks(&parsed.blocks, &parsed.spans).unwrap();
+        let blocks = expand(parsed.blocks, &HashMap::new()).unwrap();
+        let inlined = modules::inline(blocks, &dir, &HashMap::new(), Mode::Strict).unwrap();
+        let model = Model::from_resources(&only_resources(inlined.blocks)).unwrap();
+        let config = generate(&model, &moves);
+
+        let moved: Vec<String> = config
+            .blocks
+            .iter()
+            .filter(|b| b.kind == "moved")
+            .map(|b| b.to_string())
+            .collect();
+        assert_eq!(
+            moved,
+            vec![
+                "moved {
+  from = aws_lexv2models_slot_type.age
+  to   = aws_lexv2models_slot_type.default_age_field_0_age
+}
+",
+                "moved {
+  from = aws_lexv2models_slot_type.default_age_field_0_age
+  to   = aws_lexv2models_slot_type.age_field_0_age
+}
+",
+            ]
+        );
+    }
+
+    #[test]
+    fn generate_the_development_pre_survey() {
+        let source = std::fs::read_to_string("./development_pre_survey.tf").unwrap();
+        let config = generate_from(&source);
+
+        let count = |res_type: &str| {
+            config
+                .blocks
+                .iter()
+                .filter(|b| b.labels.first().map(String::as_str) == Some(res_type))
+                .count()
+        };
+
+        assert_eq!(count(LEX_BOT), 1);
+        assert_eq!(count(LEX_BOT_LOCALE), 1);
+        assert_eq!(count(LEX_INTENT), 9);
+        assert_eq!(count(LEX_SLOT_TYPE), 3);
+        assert_eq!(count(LEX_SLOT), 4);
+        assert_eq!(count(TERRAFORM_DATA), 3);
+        assert_eq!(
+            config.summary,
+            vec![
+                "Twilio.YES_NO has no Amazon equivalent, the questions of \
+                twilio_autopilot_assistants_v1.development_pre_survey using it get the custom slot \
+                type YesNo",
+                "the slot priorities of the intents of \
+                twilio_autopilot_assistants_v1.development_pre_survey are set with the AWS CLI and \
+                jq, which `terraform apply` needs",
+            ]
+        );
+        assert!(config
+            .to_hcl()
+            .contains("parent_intent_signature = \"AMAZON.FallbackIntent\""));
+        // Once for all the samples of the group
+        assert_eq!(
+            config
+                .warnings
+                .iter()
+                .filter(|w| w.contains("its utterances are dropped"))
+                .collect::<Vec<_>>(),
+            vec![
+                "twilio_autopilot_assistants_tasks_samples_v1.development_pre_survey_greeting_group \
+                does not reference a task of twilio_autopilot_assistants_v1.development_pre_survey, \
+                its utterances are dropped",
+                "twilio_autopilot_assistants_tasks_samples_v1.development_pre_survey_goodbye_group \
+                does not reference a task of twilio_autopilot_assistants_v1.development_pre_survey, \
+                its utterances are dropped",
+            ]
+        );
+    }
+}
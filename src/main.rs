@@ -1,21 +1,273 @@
-use std::fs;
+use std::{env, error::Error, fs, path::Path};
 
+mod actions;
 mod ast;
+mod autopilot;
+mod error;
+mod eval;
+mod expand;
+mod export;
 mod json_utils;
+mod lex;
+mod modules;
+mod moved;
 mod parser;
+mod references;
+#[cfg(test)]
+mod test_utils;
+mod tf_json;
+mod variables;
+mod workspace;
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let autopilot_source = fs::read_to_string("./development_pre_survey.tf")?;
+const DEFAULT_INPUT: &str = "./development_pre_survey.tf";
+const DEFAULT_OUTPUT: &str = "./lex.tf";
 
-    match parser::parse_resources(&autopilot_source) {
-        Ok((rest, parsed_resources)) => {
-            println!("The rest is {:#?}", rest);
-            println!("The parsed resources are {:#?}", parsed_resources);
-            parsed_resources.iter().for_each(|r| println!("{}", r.res_name));
-            println!("The count of parsed resources is {}", parsed_resources.len());
+#[derive(Debug, PartialEq)]
+struct Args {
+    mode: parser::Mode,
+    var_options: Vec<variables::VarOption>,
+    input: String,
+    output: String,
+}
+
+impl Args {
+    /// Options may come before, after or between the paths
+    fn parse(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+        let mut mode = parser::Mode::Strict;
+        let mut var_options = vec![];
+        let mut paths = vec![];
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            // Both `-var name=value` and `-var=name=value`, like Terraform
+            let (option, inline_value) = match arg.split_once('=') {
+                Some((option, value)) if option.starts_with('-') => {
+                    (option, Some(value.to_string()))
+                }
+                _ => (arg.as_str(), None),
+            };
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| format!("{} expects a value", option))
+            };
+
+            match option {
+                "--lenient" => mode = parser::Mode::Lenient,
+                "-var-file" => var_options.push(variables::VarOption::File(value()?.into())),
+                "-var" => {
+                    let value = value()?;
+                    let (name, value) = value
+                        .split_once('=')
+                        .ok_or_else(|| format!("-var expects name=value, got {}", value))?;
+                    var_options.push(variables::VarOption::Value(
+                        name.to_string(),
+                        value.to_string(),
+                    ));
+                }
+                option if option.starts_with('-') => {
+                    return Err(format!("unknown option {}", option))
+                }
+                _ => paths.push(arg),
+            }
         }
-        Err(err) => println!("Parsing went wrong: {}", err),
+
+        let mut paths = paths.into_iter();
+        Ok(Args {
+            mode,
+            var_options,
+            input: paths.next().unwrap_or_else(|| DEFAULT_INPUT.to_string()),
+            output: paths.next().unwrap_or_else(|| DEFAULT_OUTPUT.to_string()),
+        })
+    }
+}
+
+/// Usage: `ap2lex-tf [--lenient] [-var NAME=VALUE]... [-var-file FILE]... [INPUT] [OUTPUT]`
+///
+/// With `--lenient`, blocks of an unsupported type are skipped with a warning instead of failing
+/// the conversion. Variables are read like Terraform does, see `variables::resolve`.
+/// `INPUT` is a `.tf` or `.tf.json` file, a directory to convert all the files of, or the `.json`
+/// export of an assistant.
+fn main() -> Result<(), Box<dyn Error>> {
+    let Args {
+        mode,
+        var_options,
+        input,
+        output,
+    } = Args::parse(env::args().skip(1))?;
+
+    let read = match input.ends_with(".json") && !input.ends_with(".tf.json") {
+        true => read_export(&input).map(|model| (model, moved::Moves::default())),
+        false => read_configuration(&input, mode, &var_options),
+    };
+    let (model, moves) = match read {
+        Ok(read) => read,
+        Err(err) => {
+            eprintln!("{}", located(&input, err));
+            std::process::exit(1);
+        }
+    };
+
+    let lex_config = lex::generate(&model, &moves);
+    lex_config
+        .warnings
+        .iter()
+        .for_each(|w| eprintln!("Warning: {}", w));
+
+    fs::write(&output, lex_config.to_hcl())?;
+    println!("The Lex configuration was written to {}", output);
+    let dir = match Path::new(&output).parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    for (name, contents) in &lex_config.files {
+        fs::write(dir.join(name), contents)?;
+        println!("{} was written next to it", name);
+    }
+    if !lex_config.memory_keys.is_empty() {
+        println!("Memory keys, now session attributes:");
+        for (key, places) in &lex_config.memory_keys {
+            println!("- {}: set by {}", key, places.join(", "));
+        }
+    }
+    if !lex_config.summary.is_empty() {
+        println!("Migration summary:");
+        lex_config.summary.iter().for_each(|s| println!("- {}", s));
     }
 
     Ok(())
 }
+
+/// The model of the Autopilot resources of a Terraform configuration, and its `moved` blocks
+fn read_configuration(
+    input: &str,
+    mode: parser::Mode,
+    var_options: &[variables::VarOption],
+) -> Result<(autopilot::Model, moved::Moves), Box<dyn Error>> {
+    let workspace = workspace::Workspace::load(Path::new(input), mode)?;
+    workspace
+        .warnings
+        .iter()
+        .for_each(|w| eprintln!("Warning: {}", w));
+
+    let variables = variables::resolve(&workspace.blocks, &workspace.dir, var_options)?;
+    let moves = moved::Moves::from_blocks(&workspace.blocks, &workspace.spans)?;
+    let blocks = expand::expand(workspace.blocks, &variables)?;
+
+    let inlined = modules::inline(blocks, &workspace.dir, &variables, mode)?;
+    inlined
+        .warnings
+        .iter()
+        .for_each(|w| eprintln!("Warning: {}", w));
+
+    let parsed_resources = parser::only_resources(inlined.blocks);
+    println!(
+        "The count of parsed resources is {}",
+        parsed_resources.len()
+    );
+
+    moves
+        .check(&parsed_resources)
+        .iter()
+        .for_each(|w| eprintln!("Warning: {}", w));
+
+    references::ResourceGraph::resolve(&parsed_resources)
+        .dangling
+        .iter()
+        .for_each(|d| eprintln!("Warning: {}", d));
+
+    let model = autopilot::Model::from_resources(&parsed_resources)?;
+    Ok((model, moves))
+}
+
+/// The model of an assistant exported from Autopilot
+fn read_export(input: &str) -> Result<autopilot::Model, Box<dyn Error>> {
+    let source = fs::read_to_string(input)?;
+    Ok(export::read_export(input, &source)?)
+}
+
+/// The message of an error reading `input`, which names it unless the error already points into
+/// one of its files
+fn located(input: &str, err: Box<dyn Error>) -> String {
+    let located = err.is::<workspace::WorkspaceError>()
+        || err.is::<moved::MovedError>()
+        || matches!(
+            err.downcast_ref::<export::ExportError>(),
+            Some(export::ExportError::Parse(_))
+        );
+    match located {
+        true => err.to_string(),
+        false => format!("{}: {}", input, err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Result<Args, String> {
+        Args::parse(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn parse_args() {
+        assert_eq!(
+            args(&[
+                "main.tf",
+                "-var",
+                "gender_field_type=safespot",
+                "--lenient",
+                "-var-file=prod.tfvars",
+                "-var=serverless_url=https://example.com?a=b",
+                "out.tf",
+            ]),
+            Ok(Args {
+                mode: parser::Mode::Lenient,
+                var_options: vec![
+                    variables::VarOption::Value(
+                        "gender_field_type".to_string(),
+                        "safespot".to_string()
+                    ),
+                    variables::VarOption::File("prod.tfvars".into()),
+                    variables::VarOption::Value(
+                        "serverless_url".to_string(),
+                        "https://example.com?a=b".to_string()
+                    ),
+                ],
+                input: "main.tf".to_string(),
+                output: "out.tf".to_string(),
+            })
+        );
+
+        assert_eq!(args(&["-var"]), Err("-var expects a value".to_string()));
+        assert_eq!(
+            args(&["-var", "name"]),
+            Err("-var expects name=value, got name".to_string())
+        );
+        assert_eq!(
+            args(&["--strict"]),
+            Err("unknown option --strict".to_string())
+        );
+    }
+
+    #[test]
+    fn name_the_input_of_errors() {
+        let missing = Path::new("missing.tf");
+        let unlocated = read_configuration("missing.tf", parser::Mode::Strict, &[]).unwrap_err();
+        assert!(!missing.exists());
+        assert_eq!(
+            located("missing.tf", unlocated),
+            format!(
+                "could not read missing.tf: {}",
+                fs::read_to_string(missing).unwrap_err()
+            )
+        );
+
+        let json = std::io::Error::new(std::io::ErrorKind::NotFound, "not found");
+        assert_eq!(
+            located("export.json", Box::new(json)),
+            "export.json: not found"
+        );
+    }
+}
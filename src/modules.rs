@@ -0,0 +1,509 @@
+use crate::{
+    ast::{Block, Body, Expression, Module, Resource},
+    eval::{self, Scope},
+    expand::{self, ExpandError},
+    parser::Mode,
+    variables::{self, VariablesError},
+    workspace::{Workspace, WorkspaceError},
+};
+use std::{
+    collections::HashMap,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+/// The arguments of a module call that aren't inputs of the module
+const META_ARGUMENTS: [&str; 6] = [
+    "source",
+    "version",
+    "count",
+    "for_each",
+    "providers",
+    "depends_on",
+];
+
+#[derive(Debug)]
+pub enum ModuleError {
+    MissingSource {
+        module: String,
+    },
+    /// The source is a registry or a remote repository, or a directory that doesn't exist
+    Unavailable {
+        module: String,
+        source: String,
+    },
+    Load(WorkspaceError),
+    Variables {
+        module: String,
+        error: Box<VariablesError>,
+    },
+    Expand(ExpandError),
+    /// A module calling itself, directly or through other modules
+    Cycle {
+        module: String,
+        dir: PathBuf,
+    },
+}
+
+impl fmt::Display for ModuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModuleError::MissingSource { module } => write!(f, "{} has no source", module),
+            ModuleError::Unavailable { module, source } => write!(
+                f,
+                "the source \"{}\" of {} is not a local directory",
+                source, module
+            ),
+            ModuleError::Load(error) => write!(f, "{}", error),
+            ModuleError::Variables { module, error } => match error.as_ref() {
+                VariablesError::Unset(names) => {
+                    write!(f, "{} is missing the inputs {}", module, names.join(", "))
+                }
+                error => write!(f, "{}: {}", module, error),
+            },
+            ModuleError::Expand(error) => write!(f, "{}", error),
+            ModuleError::Cycle { module, dir } => write!(
+                f,
+                "{} calls the module in {}, which is already being inlined",
+                module,
+                dir.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ModuleError {}
+
+/// A configuration with the resources of its modules inlined
+#[derive(Debug, Default)]
+pub struct Inlined {
+    pub blocks: Vec<Block>,
+    /// Unsupported blocks and unavailable modules, skipped in lenient mode
+    pub warnings: Vec<String>,
+}
+
+/// Replaces the calls to local modules with the resources of the modules, recursively.
+/// `blocks` must be expanded already, and `dir` is the directory they were read from.
+///
+/// The `var.*` of a module are replaced with the arguments of the call, evaluated with the
+/// `variables` of the root configuration, so its resources only reference root resources, and their addresses start with the module instance, e.g.
+/// `module.age_field[0].twilio_autopilot_assistants_field_types_v1.age`.
+pub fn inline(
+    blocks: Vec<Block>,
+    dir: &Path,
+    variables: &HashMap<String, Expression>,
+    mode: Mode,
+) -> Result<Inlined, ModuleError> {
+    let mut inlined = Inlined::default();
+    let mut call_path = vec![canonical(dir)];
+    inline_into(
+        blocks,
+        dir,
+        None,
+        variables,
+        mode,
+        &mut call_path,
+        &mut inlined,
+    )?;
+    Ok(inlined)
+}
+
+/// `call_path` holds the directories of the modules being inlined, from the root one
+fn inline_into(
+    blocks: Vec<Block>,
+    dir: &Path,
+    parent: Option<&str>,
+    variables: &HashMap<String, Expression>,
+    mode: Mode,
+    call_path: &mut Vec<PathBuf>,
+    inlined: &mut Inlined,
+) -> Result<(), ModuleError> {
+    for block in blocks {
+        let module = match block {
+            Block::Module(module) => module,
+            // Only the resources of modules are merged into the configuration
+            block if parent.is_some() && !matches!(block, Block::Resource(_) | Block::Data(_)) => {
+                continue
+            }
+            block => {
+                inlined.blocks.push(block);
+                continue;
+            }
+        };
+
+        let address = match parent {
+            Some(parent) => format!("{}.{}", parent, module.address()),
+            None => module.address(),
+        };
+        let module_dir = match local_source(&module, &address, dir) {
+            Ok(module_dir) => module_dir,
+            Err(error @ ModuleError::Unavailable { .. }) if mode == Mode::Lenient => {
+                inlined
+                    .warnings
+                    .push(format!("{}, its resources are skipped", error));
+                continue;
+            }
+            Err(error) => return Err(error),
+        };
+        if call_path.contains(&canonical(&module_dir)) {
+            return Err(ModuleError::Cycle {
+                module: address,
+                dir: module_dir,
+            });
+        }
+
+        let module_blocks = read_module(&module_dir, mode, inlined)?;
+
+        // The inputs as written by the caller, with the values of the root variables
+        let inputs = module
+            .body
+            .attributes
+            .iter()
+            .filter(|(name, _)| !META_ARGUMENTS.contains(&name.as_str()))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        let inputs =
+            variables::bind(&module_blocks, inputs).map_err(|error| ModuleError::Variables {
+                module: address.clone(),
+                error: Box::new(error),
+            })?;
+        let known_scope = Scope {
+            variables: variables.clone(),
+            ..Scope::default()
+        };
+        let known_inputs = inputs
+            .iter()
+            .map(|(name, value)| (name.clone(), eval::evaluate(value, &known_scope)))
+            .collect();
+
+        let module_blocks =
+            expand::expand(module_blocks, &known_inputs).map_err(ModuleError::Expand)?;
+        let scope = Scope {
+            variables: known_inputs,
+            ..Scope::default()
+        };
+        let module_blocks = module_blocks
+            .into_iter()
+            .map(|block| match block {
+                Block::Resource(resource) => Block::Resource(bind(resource, &address, &scope)),
+                Block::Data(resource) => Block::Data(bind(resource, &address, &scope)),
+                Block::Module(module) => Block::Module(Module {
+                    body: Body {
+                        attributes: module
+                            .body
+                            .attributes
+                            .iter()
+                            .map(|(name, value)| (name.clone(), eval::evaluate(value, &scope)))
+                            .collect(),
+                        ..module.body
+                    },
+                    ..module
+                }),
+                block => block,
+            })
+            .collect();
+
+        call_path.push(canonical(&module_dir));
+        inline_into(
+            module_blocks,
+            &module_dir,
+            Some(&address),
+            variables,
+            mode,
+            call_path,
+            inlined,
+        )?;
+        call_path.pop();
+    }
+
+    Ok(())
+}
+
+/// The same directory is the same module, however its source is written
+fn canonical(dir: &Path) -> PathBuf {
+    dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf())
+}
+
+/// The directory of a module, only local paths like `./x` or `../x` being supported
+fn local_source(module: &Module, address: &str, dir: &Path) -> Result<PathBuf, ModuleError> {
+    let source = match module.body.attributes.iter().find(|(k, _)| k == "source") {
+        Some((_, Expression::String(source))) => source,
+        _ => {
+            return Err(ModuleError::MissingSource {
+                module: address.to_string(),
+            })
+        }
+    };
+
+    let module_dir = dir.join(source);
+    match (source.starts_with("./") || source.starts_with("../")) && module_dir.is_dir() {
+        true => Ok(module_dir),
+        false => Err(ModuleError::Unavailable {
+            module: address.to_string(),
+            source: source.clone(),
+        }),
+    }
+}
+
+/// Parses the `*.tf` files of a module directory, like those of the root one
+fn read_module(dir: &Path, mode: Mode, inlined: &mut Inlined) -> Result<Vec<Block>, ModuleError> {
+    let workspace = Workspace::load(dir, mode).map_err(ModuleError::Load)?;
+    inlined.warnings.extend(workspace.warnings);
+    Ok(workspace.blocks)
+}
+
+/// Substitutes the inputs of the module in a resource, and places it in the module
+fn bind(resource: Resource, module: &str, scope: &Scope) -> Resource {
+    Resource {
+        res_def: resource
+            .res_def
+            .iter()
+            .map(|(name, value)| (name.clone(), eval::evaluate(value, scope)))
+            .collect(),
+        res_blocks: expand::nested_blocks(&resource.res_blocks, scope),
+        module: Some(module.to_string()),
+        ..resource
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        autopilot::Model,
+        parser::{self, parse_blocks},
+        references::ResourceGraph,
+        test_utils::directory,
+    };
+
+    fn inline_source(
+        dir: &Path,
+        source: &str,
+        variables: &[(&str, &str)],
+        mode: Mode,
+    ) -> Result<Inlined, ModuleError> {
+        let (rest, blocks) = parse_blocks(source).unwrap();
+        assert_eq!(rest, "");
+        let variables: HashMap<String, Expression> = variables
+            .iter()
+            .map(|(k, v)| (k.to_string(), Expression::String(v.to_string())))
+            .collect();
+        let blocks = expand::expand(blocks, &variables).unwrap();
+        inline(blocks, dir, &variables, mode)
+    }
+
+    const MAIN_TF: &str = r#"
+resource "twilio_autopilot_assistants_v1" "pre_survey" {
+  unique_name = "demo_chatbot"
+}
+
+module "default_age_field" {
+  count            = var.age_field_type == "default" ? 1 : 0
+  source           = "../terraform-modules/age-field/default"
+  bot_sid          = twilio_autopilot_assistants_v1.pre_survey.sid
+  unknown_synonyms = ["idk", "?"]
+  unknown_value    = var.unknown_value
+}
+"#;
+
+    const AGE_FIELD: &str = r#"
+variable "bot_sid" {}
+variable "unknown_value" {}
+variable "unknown_synonyms" {
+  type = list(string)
+}
+variable "language" {
+  default = "en-US"
+}
+
+resource "twilio_autopilot_assistants_field_types_v1" "age" {
+  unique_name   = "Age"
+  assistant_sid = var.bot_sid
+}
+
+resource "twilio_autopilot_assistants_field_types_field_values_v1" "unknown_age_synonym_group" {
+  for_each       = toset(var.unknown_synonyms)
+  assistant_sid  = var.bot_sid
+  field_type_sid = twilio_autopilot_assistants_field_types_v1.age.sid
+  language       = var.language
+  value          = each.key
+  synonym_of     = var.unknown_value
+}
+
+output "age_sid" {
+  value = twilio_autopilot_assistants_field_types_v1.age.sid
+}
+"#;
+
+    #[test]
+    fn inline_local_modules() {
+        let dir = directory(
+            "modules",
+            &[
+                ("root/main.tf", MAIN_TF),
+                ("terraform-modules/age-field/default/main.tf", AGE_FIELD),
+            ],
+        );
+
+        let inlined = inline_source(
+            &dir.join("root"),
+            MAIN_TF,
+            &[("age_field_type", "default"), ("unknown_value", "Unknown")],
+            Mode::Strict,
+        )
+        .unwrap();
+        let resources = parser::only_resources(inlined.blocks);
+
+        assert_eq!(
+            resources.iter().map(Resource::address).collect::<Vec<_>>(),
+            vec![
+                "twilio_autopilot_assistants_v1.pre_survey",
+                "module.default_age_field[0].twilio_autopilot_assistants_field_types_v1.age",
+                "module.default_age_field[0].twilio_autopilot_assistants_field_types_field_values_v1.unknown_age_synonym_group[\"idk\"]",
+                "module.default_age_field[0].twilio_autopilot_assistants_field_types_field_values_v1.unknown_age_synonym_group[\"?\"]",
+            ]
+        );
+
+        let synonym = &resources[2];
+        assert_eq!(
+            synonym.get("assistant_sid"),
+            Some(&Expression::traversal(
+                "twilio_autopilot_assistants_v1",
+                &["pre_survey", "sid"]
+            ))
+        );
+        assert_eq!(
+            synonym.get("language"),
+            Some(&Expression::String("en-US".to_string()))
+        );
+        assert_eq!(
+            synonym.get("synonym_of"),
+            Some(&Expression::String("Unknown".to_string()))
+        );
+
+        let graph = ResourceGraph::resolve(&resources);
+        assert!(graph.dangling.is_empty());
+        assert_eq!(graph.references.len(), 5);
+    }
+
+    #[test]
+    fn build_the_model_of_inlined_modules() {
+        let dir = directory(
+            "modules-model",
+            &[
+                ("root/main.tf", MAIN_TF),
+                ("terraform-modules/age-field/default/main.tf", AGE_FIELD),
+                (
+                    "terraform-modules/age-field/default/values.tf",
+                    r#"
+resource "twilio_autopilot_assistants_field_types_field_values_v1" "unknown_age" {
+  assistant_sid  = var.bot_sid
+  field_type_sid = twilio_autopilot_assistants_field_types_v1.age.sid
+  value          = var.unknown_value
+}
+"#,
+                ),
+            ],
+        );
+
+        let inlined = inline_source(
+            &dir.join("root"),
+            MAIN_TF,
+            &[("age_field_type", "default"), ("unknown_value", "Unknown")],
+            Mode::Strict,
+        )
+        .unwrap();
+        let model = Model::from_resources(&parser::only_resources(inlined.blocks)).unwrap();
+
+        let values: Vec<(&str, Option<&str>)> = model
+            .field_values
+            .iter()
+            .map(|v| (v.value.as_str(), v.synonym_of.as_deref()))
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                ("idk", Some("Unknown")),
+                ("?", Some("Unknown")),
+                ("Unknown", None)
+            ]
+        );
+        assert_eq!(model.field_types.len(), 1);
+    }
+
+    #[test]
+    fn skip_modules_with_a_count_of_zero() {
+        let inlined = inline_source(
+            Path::new("."),
+            MAIN_TF,
+            &[("age_field_type", "safespot"), ("unknown_value", "Unknown")],
+            Mode::Strict,
+        )
+        .unwrap();
+
+        assert_eq!(inlined.blocks.len(), 1);
+    }
+
+    #[test]
+    fn report_unavailable_modules() {
+        let variables = [("age_field_type", "default"), ("unknown_value", "Unknown")];
+
+        let error = inline_source(Path::new("."), MAIN_TF, &variables, Mode::Strict).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the source \"../terraform-modules/age-field/default\" of module.default_age_field[0] is not a local directory"
+        );
+
+        let inlined = inline_source(Path::new("."), MAIN_TF, &variables, Mode::Lenient).unwrap();
+        assert_eq!(inlined.blocks.len(), 1);
+        assert_eq!(
+            inlined.warnings,
+            vec!["the source \"../terraform-modules/age-field/default\" of module.default_age_field[0] is not a local directory, its resources are skipped"]
+        );
+    }
+
+    #[test]
+    fn report_module_cycles() {
+        let dir = directory(
+            "module-cycles",
+            &[
+                ("main.tf", r#"module "self" { source = "./" }"#),
+                ("a/main.tf", r#"module "b" { source = "../b" }"#),
+                ("b/main.tf", r#"module "a" { source = "../a" }"#),
+            ],
+        );
+
+        let error = inline_source(
+            &dir,
+            r#"module "self" { source = "./" }"#,
+            &[],
+            Mode::Strict,
+        )
+        .unwrap_err();
+        assert!(matches!(error, ModuleError::Cycle { ref module, .. } if module == "module.self"));
+
+        let error =
+            inline_source(&dir, r#"module "a" { source = "./a" }"#, &[], Mode::Strict).unwrap_err();
+        assert!(
+            matches!(error, ModuleError::Cycle { ref module, .. } if module == "module.a.module.b.module.a")
+        );
+    }
+
+    #[test]
+    fn report_missing_inputs() {
+        let dir = directory("missing-inputs", &[("modules/age/main.tf", AGE_FIELD)]);
+
+        let error = inline_source(
+            &dir,
+            r#"module "age" { source = "./modules/age" }"#,
+            &[],
+            Mode::Strict,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "module.age is missing the inputs bot_sid, unknown_synonyms, unknown_value"
+        );
+    }
+}
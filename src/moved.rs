@@ -0,0 +1,347 @@
+use crate::{
+    ast::{Block, Expression, Resource, TraversalStep},
+    error::Span,
+};
+use std::fmt;
+
+/// A `moved` block, with its addresses written like `Resource::address`, e.g.
+/// `module.age_field[0].twilio_autopilot_assistants_field_types_v1.age`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Move {
+    pub from: String,
+    pub to: String,
+}
+
+/// The `moved` blocks of a configuration, in declaration order
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Moves(pub Vec<Move>);
+
+/// A `moved` block without a valid address, located by where the block starts
+#[derive(Debug, PartialEq)]
+pub enum MovedError {
+    MissingAddress { at: Span, property: &'static str },
+    InvalidAddress { at: Span, property: &'static str },
+}
+
+impl fmt::Display for MovedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MovedError::MissingAddress { at, property } => {
+                write!(f, "{}: a moved block is missing \"{}\"", at, property)
+            }
+            MovedError::InvalidAddress { at, property } => write!(
+                f,
+                "{}: the \"{}\" of a moved block should be the address of a resource or a module",
+                at, property
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MovedError {}
+
+impl Moves {
+    /// The moves of the `moved` blocks among `blocks`, `spans` being where each of the blocks
+    /// starts
+    pub fn from_blocks(blocks: &[Block], spans: &[Span]) -> Result<Self, MovedError> {
+        let mut moves = vec![];
+        for (block, span) in blocks.iter().zip(spans) {
+            if let Block::Moved(body) = block {
+                let property_address = |property: &'static str| {
+                    let at = || span.clone();
+                    let (_, value) = body
+                        .attributes
+                        .iter()
+                        .find(|(k, _)| k == property)
+                        .ok_or_else(|| MovedError::MissingAddress { at: at(), property })?;
+                    address(value).ok_or_else(|| MovedError::InvalidAddress { at: at(), property })
+                };
+                moves.push(Move {
+                    from: property_address("from")?,
+                    to: property_address("to")?,
+                });
+            }
+        }
+        Ok(Moves(moves))
+    }
+
+    /// Where whatever was at `address` lives now. Moves apply to the instances and the contents
+    /// of what they move, the most specific one first, and may be chained.
+    pub fn current(&self, address: &str) -> String {
+        let mut address = address.to_string();
+        // A cycle of moves is an error for Terraform, it's only bounded here
+        for _ in 0..=self.0.len() {
+            let closest = self
+                .0
+                .iter()
+                .rev()
+                .filter(|m| contains(&m.from, &address))
+                .max_by_key(|m| m.from.len());
+            match closest {
+                Some(m) => address = moved_to(&address, m),
+                None => break,
+            }
+        }
+        address
+    }
+
+    /// Warnings about the moves that don't match the resources
+    pub fn check(&self, resources: &[Resource]) -> Vec<String> {
+        let addresses: Vec<String> = resources.iter().map(Resource::address).collect();
+        let declared = |address: &str| addresses.iter().any(|a| contains(address, a));
+
+        let mut warnings = vec![];
+        for m in &self.0 {
+            if declared(&m.from) {
+                warnings.push(format!(
+                    "{} is moved to {} but is still declared",
+                    m.from, m.to
+                ));
+            }
+            let current = self.current(&m.to);
+            if !declared(&current) {
+                warnings.push(format!(
+                    "{} is moved to {}, which is not declared",
+                    m.from, current
+                ));
+            }
+        }
+        warnings
+    }
+}
+
+/// Whether `address` is `outer`, an instance of it or something in it, e.g. `type.name["key"]`
+/// or `module.a[0].type.name` in `module.a`
+pub fn contains(outer: &str, address: &str) -> bool {
+    match address.strip_prefix(outer) {
+        Some(rest) => rest.is_empty() || rest.starts_with('.') || rest.starts_with('['),
+        None => false,
+    }
+}
+
+/// `address`, in what `m` moves, moved along with it
+fn moved_to(address: &str, m: &Move) -> String {
+    format!("{}{}", m.to, &address[m.from.len()..])
+}
+
+/// The modules, type and name of the resource at `address`, the modules and the name keeping
+/// their instance keys, e.g. `["age_field[0]"]`, `twilio_autopilot_assistants_v1` and `age`
+pub fn resource_of(address: &str) -> Option<(Vec<&str>, &str, &str)> {
+    // Splits on the dots outside of keys, which may hold any string
+    let mut parts = vec![];
+    let mut start = 0;
+    let mut in_key = false;
+    for (i, c) in address.char_indices() {
+        match c {
+            '[' => in_key = true,
+            ']' => in_key = false,
+            '.' if !in_key => {
+                parts.push(&address[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&address[start..]);
+
+    let mut modules = vec![];
+    let mut parts = parts.as_slice();
+    while let ["module", module, rest @ ..] = parts {
+        modules.push(*module);
+        parts = rest;
+    }
+    match parts {
+        [res_type, res_name] if !res_type.contains('[') => Some((modules, res_type, res_name)),
+        _ => None,
+    }
+}
+
+/// `address` without the keys of the instances, shared by all of them, e.g.
+/// `module.age_field.type.name` for `module.age_field[0].type.name["key"]`
+pub fn without_keys(address: &str) -> String {
+    let mut unkeyed = String::new();
+    let (mut in_key, mut in_string, mut escaped) = (false, false, false);
+    for c in address.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' if in_key => in_string = !in_string,
+            '[' if !in_key => in_key = true,
+            ']' if !in_string => in_key = false,
+            _ if !in_key => unkeyed.push(c),
+            _ => {}
+        }
+    }
+    unkeyed
+}
+
+/// The address of a traversal like `module.age_field[0].type.name`
+fn address(value: &Expression) -> Option<String> {
+    match value {
+        Expression::Traversal(root, steps) => {
+            let mut address = address(root)?;
+            for step in steps {
+                match step {
+                    TraversalStep::GetAttr(name) => address.push_str(&format!(".{}", name)),
+                    TraversalStep::Index(Expression::Number(n)) if n.fract() == 0.0 => {
+                        address.push_str(&format!("[{}]", n))
+                    }
+                    TraversalStep::Index(Expression::String(key)) => {
+                        address.push_str(&format!("[{:?}]", key))
+                    }
+                    _ => return None,
+                }
+            }
+            Some(address)
+        }
+        Expression::Identifier(name) => Some(name.clone()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{only_resources, parse_file, Mode, ParsedFile};
+
+    fn parsed(source: &str) -> ParsedFile {
+        parse_file("main.tf", source, Mode::Strict).unwrap()
+    }
+
+    fn moves(source: &str) -> Result<Moves, MovedError> {
+        let parsed = parsed(source);
+        Moves::from_blocks(&parsed.blocks, &parsed.spans)
+    }
+
+    const MOVED: &str = r#"
+moved {
+  from = twilio_autopilot_assistants_field_types_v1.age
+  to   = module.default_age_field[0].twilio_autopilot_assistants_field_types_v1.age
+}
+
+moved {
+  from = module.default_age_field
+  to   = module.age_field
+}
+
+moved {
+  from = twilio_autopilot_assistants_field_types_field_values_v1.unknown_age_group
+  to   = twilio_autopilot_assistants_field_types_field_values_v1.unknown_age_synonym_group
+}
+"#;
+
+    #[test]
+    fn follow_moves() {
+        let moves = moves(MOVED).unwrap();
+
+        assert_eq!(
+            moves.current("twilio_autopilot_assistants_field_types_v1.age"),
+            "module.age_field[0].twilio_autopilot_assistants_field_types_v1.age"
+        );
+        assert_eq!(
+            moves.current(
+                "twilio_autopilot_assistants_field_types_field_values_v1.unknown_age_group[\"idk\"]"
+            ),
+            "twilio_autopilot_assistants_field_types_field_values_v1.unknown_age_synonym_group[\"idk\"]"
+        );
+        assert_eq!(
+            moves.current("twilio_autopilot_assistants_field_types_v1.age_group"),
+            "twilio_autopilot_assistants_field_types_v1.age_group"
+        );
+    }
+
+    #[test]
+    fn follow_the_most_specific_move() {
+        let moves = moves(
+            r#"
+moved {
+  from = module.survey
+  to   = module.pre_survey
+}
+
+moved {
+  from = module.survey.twilio_autopilot_assistants_tasks_v1.greeting
+  to   = module.survey.twilio_autopilot_assistants_tasks_v1.hello
+}
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            moves.current("module.survey.twilio_autopilot_assistants_tasks_v1.greeting"),
+            "module.pre_survey.twilio_autopilot_assistants_tasks_v1.hello"
+        );
+        assert_eq!(
+            moves.current("module.survey.twilio_autopilot_assistants_tasks_v1.goodbye"),
+            "module.pre_survey.twilio_autopilot_assistants_tasks_v1.goodbye"
+        );
+    }
+
+    #[test]
+    fn check_moves() {
+        let source = format!(
+            "{}{}",
+            MOVED,
+            r#"
+resource "twilio_autopilot_assistants_field_types_v1" "age" {
+  unique_name = "Age"
+}
+
+resource "twilio_autopilot_assistants_field_types_field_values_v1" "unknown_age_synonym_group" {
+  for_each = toset(["idk"])
+  value    = each.key
+}
+"#
+        );
+        let parsed = parsed(&source);
+        let moves = Moves::from_blocks(&parsed.blocks, &parsed.spans).unwrap();
+        let blocks = crate::expand::expand(parsed.blocks, &Default::default()).unwrap();
+
+        assert_eq!(
+            moves.check(&only_resources(blocks)),
+            vec![
+                "twilio_autopilot_assistants_field_types_v1.age is moved to module.default_age_field[0].twilio_autopilot_assistants_field_types_v1.age but is still declared",
+                "twilio_autopilot_assistants_field_types_v1.age is moved to module.age_field[0].twilio_autopilot_assistants_field_types_v1.age, which is not declared",
+                "module.default_age_field is moved to module.age_field, which is not declared",
+            ]
+        );
+    }
+
+    #[test]
+    fn find_resources_in_addresses() {
+        assert_eq!(
+            resource_of("module.a[\"x.y\"].module.b.twilio_autopilot_assistants_v1.survey[0]"),
+            Some((
+                vec!["a[\"x.y\"]", "b"],
+                "twilio_autopilot_assistants_v1",
+                "survey[0]"
+            ))
+        );
+        assert_eq!(resource_of("module.a[0]"), None);
+    }
+
+    #[test]
+    fn remove_instance_keys() {
+        assert_eq!(
+            without_keys("module.a[0].module.b[\"x]\\\"y\"].type.name[\"key\"]"),
+            "module.a.module.b.type.name"
+        );
+        assert_eq!(without_keys("type.name"), "type.name");
+    }
+
+    #[test]
+    fn report_invalid_moves() {
+        assert_eq!(
+            moves("moved {\n  from = \"age\"\n  to = age\n}")
+                .unwrap_err()
+                .to_string(),
+            "main.tf:1:1: the \"from\" of a moved block should be the address of a resource or a module"
+        );
+        assert_eq!(
+            moves("moved {\n  from = a.b\n  to = a.c\n}\n\nmoved {\n  from = a.b\n}")
+                .unwrap_err()
+                .to_string(),
+            "main.tf:6:1: a moved block is missing \"to\""
+        );
+    }
+}
@@ -1,25 +1,64 @@
 use crate::{
-    ast::{Resource, ResourceDefinition, ResourceProperty, ResourcePropertyValue},
+    ast::{
+        BinaryOperator, Block, Body, Expression, ForExpression, Module, NestedBlock, Resource,
+        ResourceDefinition, ResourceProperty, TemplatePart, TraversalStep, UnaryOperator,
+    },
+    error::{Diagnostic, ParserError, Span},
     json_utils,
 };
 use nom::{
     branch::alt,
-    bytes::complete::{is_not, tag, take_until, take_while},
-    character::complete::{char, multispace0},
-    combinator::{cut, map, value},
-    error::{Error, ParseError},
+    bytes::complete::{tag, take_until, take_while, take_while1, take_while_m_n},
+    character::complete::{
+        char, digit1, line_ending, multispace1, not_line_ending, one_of, satisfy, space0,
+    },
+    combinator::{cut, eof, map, map_opt, map_res, not, opt, peek, recognize, value},
+    error::{context, ErrorKind, ParseError},
     multi::{many0, separated_list0, separated_list1},
-    sequence::{delimited, preceded, separated_pair, terminated, tuple},
-    IResult,
+    sequence::{delimited, pair, preceded, separated_pair, terminated, tuple},
+    Err,
 };
 
-/// A combinator that takes a parser `inner` and produces a parser that also consumes both leading and
-/// trailing whitespace, returning the output of `inner`.
+type IResult<I, O, E = ParserError<I>> = nom::IResult<I, O, E>;
+
+/// Parses a `# ...`, `// ...` or `/* ... */` comment, returning its text without the delimiters
+pub fn comment<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, &'a str, E> {
+    alt((
+        preceded(alt((tag("#"), tag("//"))), not_line_ending),
+        delimited(tag("/*"), take_until("*/"), tag("*/")),
+    ))(i)
+}
+
+/// Consumes any whitespace and comments, keeping the text of the comments
+fn comments<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, Vec<String>, E> {
+    map(
+        many0(alt((map(multispace1, |_| None), map(comment, Some)))),
+        |comments| {
+            comments
+                .into_iter()
+                .flatten()
+                .map(|c| c.trim().to_string())
+                .collect()
+        },
+    )(i)
+}
+
+/// A combinator that takes a parser `inner` and produces a parser that also consumes both leading
+/// whitespace and comments, and trailing spaces, returning the output of `inner`.
+/// Comments and line breaks after `inner` are left for the next parser, so comments can be attached to
+/// what follows them and attribute values can be told apart by the end of their line.
 fn ws<'a, F, O, E: ParseError<&'a str>>(inner: F) -> impl FnMut(&'a str) -> IResult<&'a str, O, E>
 where
     F: FnMut(&'a str) -> IResult<&'a str, O, E>,
 {
-    delimited(multispace0, inner, multispace0)
+    delimited(comments, inner, space0)
+}
+
+fn parse_identifier(i: &str) -> IResult<&str, &str> {
+    ws(recognize(pair(
+        satisfy(|c| c.is_alphabetic() || c == '_'),
+        take_while(|c: char| c.is_alphanumeric() || c == '_' || c == '-'),
+    )))(i)
 }
 
 fn parse_resource_identifier(i: &str) -> IResult<&str, &str> {
@@ -30,144 +69,735 @@ fn parse_resource_identifier(i: &str) -> IResult<&str, &str> {
 }
 
 fn parse_resource_property_key(i: &str) -> IResult<&str, String> {
-    map(ws(take_while(|c| !(c == ' ' || c == '='))), |s| {
-        s.to_string()
-    })(i)
+    map(parse_identifier, str::to_string)(i)
 }
 
-// Parsers used for the value of a single resource property
+// Parsers used for templates, i.e. quoted strings and heredocs
 //
 
-fn parse_vnull(i: &str) -> IResult<&str, ()> {
-    ws(value((), tag("null")))(i)
+/// Parses the escape sequence after a `\` in a quoted string
+fn parse_escape(i: &str) -> IResult<&str, char> {
+    let unicode = |digits: usize| {
+        map_opt(
+            take_while_m_n(digits, digits, |c: char| c.is_ascii_hexdigit()),
+            |hex| u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+        )
+    };
+
+    alt((
+        value('\n', char('n')),
+        value('\r', char('r')),
+        value('\t', char('t')),
+        value('"', char('"')),
+        value('\\', char('\\')),
+        preceded(char('u'), unicode(4)),
+        preceded(char('U'), unicode(8)),
+    ))(i)
+}
+
+/// Parses literal text and `${...}` interpolations, up to the closing `"` of a quoted string or
+/// the end of the input for the body of a heredoc.
+/// `$${` and `%%{` are the escaped forms of a literal `${` and `%{`.
+fn parse_template_parts(i: &str, quoted: bool) -> IResult<&str, Vec<TemplatePart>> {
+    let mut i = i;
+    let mut parts = vec![];
+    let mut literal = String::new();
+    loop {
+        if quoted && i.starts_with('"') || !quoted && i.is_empty() {
+            break;
+        } else if let Some(rest) = i.strip_prefix('\\').filter(|_| quoted) {
+            let (rest, c) = cut(parse_escape)(rest)?;
+            literal.push(c);
+            i = rest;
+        } else if let Some(rest) = i.strip_prefix("$${") {
+            literal.push_str("${");
+            i = rest;
+        } else if let Some(rest) = i.strip_prefix("%%{") {
+            literal.push_str("%{");
+            i = rest;
+        } else if i.starts_with("${") {
+            let (rest, expression) = context(
+                "interpolation",
+                delimited(
+                    pair(tag("${"), opt(char('~'))),
+                    cut(parse_expression),
+                    // Unlike `ws`, the spaces after the `}` are part of the text
+                    cut(preceded(comments, pair(opt(char('~')), char('}')))),
+                ),
+            )(i)?;
+            if !literal.is_empty() {
+                parts.push(TemplatePart::Literal(std::mem::take(&mut literal)));
+            }
+            parts.push(TemplatePart::Interpolation(expression));
+            i = rest;
+        } else if let Some(c) = i.chars().next().filter(|c| !quoted || *c != '\n') {
+            literal.push(c);
+            i = &i[c.len_utf8()..];
+        } else {
+            // Quoted strings can't span several lines
+            return Err(Err::Failure(ParserError::from_char(i, '"')));
+        }
+    }
+
+    if !literal.is_empty() || parts.is_empty() {
+        parts.push(TemplatePart::Literal(literal));
+    }
+
+    Ok((i, parts))
 }
 
-fn parse_vbool(i: &str) -> IResult<&str, bool> {
-    let parse_true = value(true, tag("true"));
-    let parse_false = value(false, tag("false"));
+/// Parses text that was already unquoted, e.g. a JSON string, into its literal text and
+/// `${...}` interpolations
+pub fn parse_template_text(text: &str) -> IResult<&str, Vec<TemplatePart>> {
+    parse_template_parts(text, false)
+}
 
-    ws(alt((parse_true, parse_false)))(i)
+/// Parses a quoted string into its literal text and `${...}` interpolations
+pub fn parse_template(i: &str) -> IResult<&str, Vec<TemplatePart>> {
+    context(
+        "string",
+        delimited(char('"'), |i| parse_template_parts(i, true), cut(char('"'))),
+    )(i)
 }
 
-fn parse_string(i: &str) -> IResult<&str, String> {
-    let (i, s) = ws(delimited(char('\"'), is_not("\""), char('\"')))(i)?;
+/// The text of a template without interpolations
+pub fn literal_template(parts: &[TemplatePart]) -> Option<String> {
+    parts
+        .iter()
+        .map(|part| match part {
+            TemplatePart::Literal(s) => Some(s.as_str()),
+            TemplatePart::Interpolation(_) => None,
+        })
+        .collect()
+}
 
-    Ok((i, s.to_string()))
+pub fn template_expression(parts: Vec<TemplatePart>) -> Expression {
+    match literal_template(&parts) {
+        Some(s) => Expression::String(s),
+        None => Expression::Template(parts),
+    }
 }
 
-fn parse_vstring(i: &str) -> IResult<&str, String> {
-    parse_string(i)
+/// Parses a `<<EOF` heredoc, or a `<<-EOF` one whose lines are stripped of their common indentation
+fn parse_heredoc(i: &str) -> IResult<&str, Expression> {
+    context("heredoc", parse_heredoc_body)(i)
 }
 
-fn parse_vjson(i: &str) -> IResult<&str, json_utils::JsonValue> {
-    ws(preceded(
-        tag("jsonencode("),
-        terminated(json_utils::root, char(')')),
-    ))(i)
+fn parse_heredoc_body(i: &str) -> IResult<&str, Expression> {
+    let (i, (indented, delimiter)) = delimited(
+        tag("<<"),
+        pair(
+            map(opt(char('-')), |dash| dash.is_some()),
+            take_while1(|c: char| c.is_alphanumeric() || c == '_'),
+        ),
+        line_ending,
+    )(i)?;
+
+    let mut lines = vec![];
+    let mut rest = i;
+    loop {
+        if rest.is_empty() {
+            return Err(Err::Failure(ParserError::from_error_kind(
+                i,
+                ErrorKind::Eof,
+            )));
+        }
+        let (next, line) = terminated(not_line_ending, opt(line_ending))(rest)?;
+        if line.trim() == delimiter {
+            // The value ends right after the delimiter, before its line break
+            let (after_delimiter, _) = preceded(space0, tag(delimiter))(rest)?;
+            rest = after_delimiter;
+            break;
+        }
+        lines.push(line);
+        rest = next;
+    }
+
+    let indentation = match indented {
+        true => lines
+            .iter()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| l.len() - l.trim_start().len())
+            .min()
+            .unwrap_or(0),
+        false => 0,
+    };
+    let body: String = lines
+        .iter()
+        .map(|l| format!("{}\n", l.get(indentation..).unwrap_or("")))
+        .collect();
+
+    match parse_template_parts(&body, false) {
+        Ok((_, parts)) => Ok((rest, template_expression(parts))),
+        Err(_) => Err(Err::Failure(ParserError::from_error_kind(
+            i,
+            ErrorKind::Verify,
+        ))),
+    }
 }
 
-// Only parses an array of strings for now
-fn parse_array(i: &str) -> IResult<&str, Vec<String>> {
-    delimited(
-        ws(char('[')),
-        separated_list0(ws(char(',')), parse_string),
-        ws(char(']')),
+// Parsers used for expressions, e.g. the value of a single resource property
+//
+
+fn parse_number(i: &str) -> IResult<&str, f64> {
+    map_res(
+        recognize(tuple((
+            digit1,
+            opt(pair(char('.'), digit1)),
+            opt(tuple((one_of("eE"), opt(one_of("+-")), digit1))),
+        ))),
+        str::parse,
     )(i)
 }
 
-fn parse_vset(i: &str) -> IResult<&str, Vec<String>> {
-    ws(preceded(
-        tag("toset("),
+/// `jsonencode(...)` of a literal JSON document keeps it as a `JsonValue`. Arguments that aren't
+/// plain JSON (e.g. `jsonencode(local.actions)`) are left to be parsed as any other function call,
+/// but a malformed JSON document is reported as such.
+fn parse_json_call(i: &str) -> IResult<&str, json_utils::JsonValue> {
+    let (i, _) = tag("jsonencode(")(i)?;
+    let result = terminated(json_utils::root, ws(char(')')))(i);
+
+    match result {
+        Err(Err::Failure(e)) if !is_json_document(i) => Err(Err::Error(e)),
+        result => result,
+    }
+}
+
+/// Whether the argument of `jsonencode(...)` is meant as JSON rather than HCL: an object whose
+/// first key is quoted and followed by anything but `=`, e.g. `{ "actions" : [...] }`
+fn is_json_document(i: &str) -> bool {
+    let first_key = preceded(ws(char('{')), ws(json_utils::string::<ParserError<&str>>))(i);
+    match first_key {
+        Ok((rest, _)) => !rest.trim_start().starts_with('='),
+        Err(_) => false,
+    }
+}
+
+fn parse_function_call(i: &str) -> IResult<&str, Expression> {
+    let (i, name) = terminated(
+        recognize(separated_list1(tag("::"), parse_identifier)),
+        char('('),
+    )(i)?;
+
+    let (i, (args, expand_final)) = context(
+        "function call",
         cut(terminated(
-            parse_array,
-            // This would change return signature to Vec<JsonValue>, but I'm not sure if tf sets are json valid format, we'll find out :)
-            // json_utils::array,
-            char(')'),
+            pair(
+                separated_list0(ws(char(',')), parse_expression),
+                map(opt(ws(tag("..."))), |dots| dots.is_some()),
+            ),
+            pair(opt(ws(char(','))), ws(char(')'))),
         )),
-    ))(i)
+    )(i)?;
+
+    Ok((
+        i,
+        Expression::FunctionCall {
+            name: name.to_string(),
+            args,
+            expand_final,
+        },
+    ))
+}
+
+fn parse_keyword<'a>(keyword: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    ws(terminated(
+        tag(keyword),
+        not(satisfy(|c| c.is_alphanumeric() || c == '_' || c == '-')),
+    ))
 }
 
-// Only parses usage of .key for now
-fn parse_veach(i: &str) -> IResult<&str, &str> {
-    ws(tag("each.key"))(i)
+/// The part of a `for` expression after the opening bracket and up to the closing one
+fn parse_for(i: &str, object: bool) -> IResult<&str, ForExpression> {
+    let (i, _) = parse_keyword("for")(i)?;
+
+    let (i, (first_var, second_var, _, collection, _)) = cut(tuple((
+        parse_identifier,
+        opt(preceded(ws(char(',')), parse_identifier)),
+        parse_keyword("in"),
+        parse_expression,
+        ws(char(':')),
+    )))(i)?;
+
+    let (i, (key, value, grouping)) = match object {
+        true => cut(map(
+            tuple((
+                parse_expression,
+                ws(tag("=>")),
+                parse_expression,
+                opt(ws(tag("..."))),
+            )),
+            |(key, _, value, grouping)| (Some(key), value, grouping.is_some()),
+        ))(i)?,
+        false => map(cut(parse_expression), |value| (None, value, false))(i)?,
+    };
+
+    let (i, condition) = opt(preceded(parse_keyword("if"), cut(parse_expression)))(i)?;
+
+    let (key_var, value_var) = match second_var {
+        Some(second_var) => (Some(first_var.to_string()), second_var.to_string()),
+        None => (None, first_var.to_string()),
+    };
+
+    Ok((
+        i,
+        ForExpression {
+            key_var,
+            value_var,
+            collection,
+            key,
+            value,
+            condition,
+            grouping,
+        },
+    ))
 }
 
-fn parse_vreference(i: &str) -> IResult<&str, (String, String)> {
-    let no_dot_or_whitespace = |c: char| c != '.' && !c.is_ascii_whitespace();
+fn parse_tuple(i: &str) -> IResult<&str, Expression> {
+    context(
+        "tuple",
+        delimited(
+            char('['),
+            alt((
+                map(|i| parse_for(i, false), |f| Expression::For(Box::new(f))),
+                map(
+                    terminated(
+                        separated_list0(ws(char(',')), parse_expression),
+                        opt(ws(char(','))),
+                    ),
+                    Expression::Tuple,
+                ),
+            )),
+            cut(ws(char(']'))),
+        ),
+    )(i)
+}
 
-    map(
-        ws(tuple((
-            separated_pair(
-                take_while(no_dot_or_whitespace),
-                char('.'),
-                take_while(no_dot_or_whitespace),
+fn parse_object_item(i: &str) -> IResult<&str, (Expression, Expression)> {
+    let (i, (key, _, value)) = tuple((
+        parse_expression,
+        cut(ws(alt((char('='), char(':'))))),
+        cut(parse_expression),
+    ))(i)?;
+
+    // Bare names are literal keys, not references
+    let key = match key {
+        Expression::Identifier(name) => Expression::String(name),
+        key => key,
+    };
+
+    Ok((i, (key, value)))
+}
+
+fn parse_object(i: &str) -> IResult<&str, Expression> {
+    context(
+        "object",
+        delimited(
+            char('{'),
+            alt((
+                map(|i| parse_for(i, true), |f| Expression::For(Box::new(f))),
+                map(
+                    many0(terminated(parse_object_item, opt(ws(char(','))))),
+                    Expression::Object,
+                ),
+            )),
+            cut(ws(char('}'))),
+        ),
+    )(i)
+}
+
+fn parse_primary(i: &str) -> IResult<&str, Expression> {
+    ws(alt((
+        map(parse_number, Expression::Number),
+        map(parse_template, template_expression),
+        parse_heredoc,
+        map(parse_json_call, Expression::Json),
+        parse_tuple,
+        parse_object,
+        map(
+            delimited(char('('), cut(parse_expression), cut(ws(char(')')))),
+            |e| Expression::Parenthesized(Box::new(e)),
+        ),
+        parse_function_call,
+        map(parse_identifier, |name| match name {
+            "true" => Expression::Boolean(true),
+            "false" => Expression::Boolean(false),
+            "null" => Expression::Null,
+            name => Expression::Identifier(name.to_string()),
+        }),
+    )))(i)
+}
+
+fn parse_traversal_step(i: &str) -> IResult<&str, TraversalStep> {
+    terminated(
+        alt((
+            preceded(
+                // Not the `...` expanding the last argument of a function call
+                terminated(char('.'), not(char('.'))),
+                cut(alt((
+                    value(TraversalStep::AttrSplat, char('*')),
+                    map(digit1, |index: &str| {
+                        TraversalStep::Index(Expression::Number(index.parse().unwrap_or(0.0)))
+                    }),
+                    map(parse_identifier, |name| {
+                        TraversalStep::GetAttr(name.to_string())
+                    }),
+                ))),
             ),
-            take_while(|c: char| !c.is_ascii_whitespace()),
-        ))),
-        |((res_type, res_name), _rest)| (res_type.to_string(), res_name.to_string()),
+            delimited(
+                char('['),
+                alt((
+                    value(TraversalStep::FullSplat, ws(char('*'))),
+                    map(parse_expression, TraversalStep::Index),
+                )),
+                cut(ws(char(']'))),
+            ),
+        )),
+        space0,
+    )(i)
+}
+
+fn parse_postfix(i: &str) -> IResult<&str, Expression> {
+    map(
+        pair(parse_primary, many0(parse_traversal_step)),
+        |(root, steps)| match steps.is_empty() {
+            true => root,
+            false => Expression::Traversal(Box::new(root), steps),
+        },
     )(i)
 }
 
-fn parse_resource_property_value(i: &str) -> IResult<&str, ResourcePropertyValue> {
+fn parse_unary(i: &str) -> IResult<&str, Expression> {
     alt((
-        map(parse_vjson, ResourcePropertyValue::VJson),
-        map(parse_vset, ResourcePropertyValue::VSet),
-        map(parse_vstring, ResourcePropertyValue::VString),
-        map(parse_vreference, |(res_type, res_name)| {
-            ResourcePropertyValue::VReference { res_type, res_name }
-        }),
-        map(parse_veach, |_| ResourcePropertyValue::VEach),
-        map(parse_vbool, ResourcePropertyValue::VBoolean),
-        map(parse_vnull, |_| ResourcePropertyValue::VNull),
+        map(
+            pair(
+                ws(alt((
+                    value(UnaryOperator::Not, char('!')),
+                    value(UnaryOperator::Negate, char('-')),
+                ))),
+                cut(parse_unary),
+            ),
+            |(op, operand)| Expression::Unary(op, Box::new(operand)),
+        ),
+        parse_postfix,
     ))(i)
 }
 
-fn parse_resource_definition_property(i: &str) -> IResult<&str, ResourceProperty> {
-    let (i, (key, _, value)) = tuple((
-        parse_resource_property_key,
-        ws(char('=')),
-        parse_resource_property_value,
+fn parse_binary_operator(i: &str) -> IResult<&str, BinaryOperator> {
+    ws(alt((
+        value(BinaryOperator::Or, tag("||")),
+        value(BinaryOperator::And, tag("&&")),
+        value(BinaryOperator::Equal, tag("==")),
+        value(BinaryOperator::NotEqual, tag("!=")),
+        value(BinaryOperator::LessOrEqual, tag("<=")),
+        value(BinaryOperator::GreaterOrEqual, tag(">=")),
+        value(BinaryOperator::Less, char('<')),
+        value(BinaryOperator::Greater, char('>')),
+        value(BinaryOperator::Add, char('+')),
+        value(BinaryOperator::Subtract, char('-')),
+        value(BinaryOperator::Multiply, char('*')),
+        value(BinaryOperator::Divide, char('/')),
+        value(BinaryOperator::Modulo, char('%')),
+    )))(i)
+}
+
+/// Parses operations whose operators bind at least as tight as `min_precedence`, left associatively
+fn parse_binary(i: &str, min_precedence: u8) -> IResult<&str, Expression> {
+    let (mut i, mut lhs) = parse_unary(i)?;
+
+    loop {
+        match parse_binary_operator(i) {
+            Ok((rest, op)) if op.precedence() >= min_precedence => {
+                let (rest, rhs) = cut(|i| parse_binary(i, op.precedence() + 1))(rest)?;
+                lhs = Expression::Binary(op, Box::new(lhs), Box::new(rhs));
+                i = rest;
+            }
+            _ => return Ok((i, lhs)),
+        }
+    }
+}
+
+pub fn parse_expression(i: &str) -> IResult<&str, Expression> {
+    let (i, condition) = parse_binary(i, 0)?;
+
+    let (i, branches) = opt(preceded(
+        ws(char('?')),
+        cut(separated_pair(
+            parse_expression,
+            ws(char(':')),
+            parse_expression,
+        )),
     ))(i)?;
 
-    Ok((i, (key, value)))
+    match branches {
+        Some((then, otherwise)) => Ok((
+            i,
+            Expression::Conditional {
+                condition: Box::new(condition),
+                then: Box::new(then),
+                otherwise: Box::new(otherwise),
+            },
+        )),
+        None => Ok((i, condition)),
+    }
 }
 
-fn parse_resource_definition(i: &str) -> IResult<&str, ResourceDefinition> {
-    let (i, _) = ws(char('{'))(i)?;
+/// Attributes end with their line, so a value is only complete if nothing but a comment follows it
+/// (or the end of the block it is in)
+fn end_of_value(i: &str) -> IResult<&str, ()> {
+    value(
+        (),
+        peek(preceded(
+            space0,
+            alt((line_ending, eof, tag("}"), tag("#"), tag("//"), tag("/*"))),
+        )),
+    )(i)
+}
 
-    let (i, definition_properties) = many0(parse_resource_definition_property)(i)?;
+fn parse_resource_definition_property(i: &str) -> IResult<&str, ResourceProperty> {
+    let (i, (key, _)) = pair(parse_resource_property_key, ws(char('=')))(i)?;
 
-    let (i, _) = ws(char('}'))(i)?;
+    // Past the `=` this can only be an attribute, whose value must end with its line
+    let (i, value) = context("attribute", cut(terminated(parse_expression, end_of_value)))(i)?;
 
-    Ok((i, definition_properties))
+    Ok((i, (key, value)))
 }
 
-fn parse_resource(i: &str) -> IResult<&str, Resource> {
-    let (i, (_, res_type, res_name, res_def)) = ws(tuple((
-        tag("resource"),
-        parse_resource_identifier,
-        parse_resource_identifier,
-        parse_resource_definition,
-    )))(i)?;
+enum BodyItem {
+    Attribute(ResourceProperty),
+    Block(NestedBlock),
+}
+
+fn parse_nested_block(i: &str) -> IResult<&str, NestedBlock> {
+    let (i, (kind, labels, body)) = tuple((
+        parse_identifier,
+        many0(parse_resource_identifier),
+        parse_block_body,
+    ))(i)?;
 
     Ok((
         i,
-        Resource {
-            res_type: res_type.to_string(),
-            res_def,
-            res_name: res_name.to_string(),
+        NestedBlock {
+            kind: kind.to_string(),
+            labels: labels.into_iter().map(str::to_string).collect(),
+            body,
         },
     ))
 }
 
-pub fn parse_resources(i: &str) -> IResult<&str, Vec<Resource>> {
-    let (i, _o) = take_until("resource")(i)?;
+fn parse_block_body(i: &str) -> IResult<&str, Body> {
+    let (i, _) = ws(char('{'))(i)?;
+
+    let (i, items) = many0(alt((
+        map(parse_resource_definition_property, BodyItem::Attribute),
+        map(parse_nested_block, BodyItem::Block),
+    )))(i)?;
 
-    let (i, o) = many0(parse_resource)(i)?;
+    let (i, _) = cut(ws(char('}')))(i)?;
+
+    let mut body = Body {
+        attributes: vec![],
+        blocks: vec![],
+        comments: vec![],
+    };
+    for item in items {
+        match item {
+            BodyItem::Attribute(attribute) => body.attributes.push(attribute),
+            BodyItem::Block(block) => body.blocks.push(block),
+        }
+    }
+
+    Ok((i, body))
+}
+
+/// Parses any top level block, e.g. `resource "type" "name" { ... }` or `moved { ... }`
+fn parse_block(i: &str) -> IResult<&str, Block> {
+    context(
+        "block",
+        map_res(
+            tuple((
+                comments,
+                parse_identifier,
+                many0(parse_resource_identifier),
+                parse_block_body,
+            )),
+            |(comments, kind, labels, mut body)| {
+                body.comments = comments;
+
+                let block = match (kind, labels.as_slice()) {
+                    ("terraform", []) => Block::Terraform(body),
+                    ("provider", [name]) => Block::Provider(name.to_string(), body),
+                    ("variable", [name]) => Block::Variable(name.to_string(), body),
+                    ("locals", []) => Block::Locals(body),
+                    ("output", [name]) => Block::Output(name.to_string(), body),
+                    ("module", [name]) => Block::Module(Module {
+                        name: name.to_string(),
+                        body,
+                        key: None,
+                    }),
+                    ("moved", []) => Block::Moved(body),
+                    ("import", []) => Block::Import(body),
+                    ("removed", []) => Block::Removed(body),
+                    ("check", [name]) => Block::Check(name.to_string(), body),
+                    ("resource", [res_type, res_name]) => {
+                        Block::Resource(resource(res_type, res_name, body))
+                    }
+                    ("data", [res_type, res_name]) => {
+                        Block::Data(resource(res_type, res_name, body))
+                    }
+                    _ => return Err(ErrorKind::Tag),
+                };
+
+                Ok(block)
+            },
+        ),
+    )(i)
+}
+
+pub fn resource(res_type: &str, res_name: &str, body: Body) -> Resource {
+    Resource {
+        res_type: res_type.to_string(),
+        res_def: body.attributes,
+        res_name: res_name.to_string(),
+        res_blocks: body.blocks,
+        comments: body.comments,
+        key: None,
+        module: None,
+    }
+}
+
+#[cfg(test)]
+pub fn parse_blocks(i: &str) -> IResult<&str, Vec<Block>> {
+    let (i, o) = many0(parse_block)(i)?;
+
+    // Trailing comments have no block to be attached to
+    let (i, _) = comments(i)?;
 
     Ok((i, o))
 }
 
+/// Parses the blocks of a source, keeping only the resources.
+/// Unlike `parse_file`, whatever could not be parsed is returned as is.
+#[cfg(test)]
+pub fn parse_resources(i: &str) -> IResult<&str, Vec<Resource>> {
+    map(parse_blocks, only_resources)(i)
+}
+
+pub fn only_resources(blocks: Vec<Block>) -> Vec<Resource> {
+    blocks
+        .into_iter()
+        .filter_map(|block| match block {
+            Block::Resource(resource) => Some(resource),
+            _ => None,
+        })
+        .collect()
+}
+
+/// How `parse_file` deals with blocks it doesn't support
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    /// Any block that can't be parsed is an error
+    Strict,
+    /// Well formed blocks of an unknown type are skipped, and reported as warnings
+    Lenient,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedFile {
+    pub blocks: Vec<Block>,
+    /// Where each of the blocks starts, past its comments
+    pub spans: Vec<Span>,
+    /// The blocks skipped in lenient mode
+    pub warnings: Vec<Diagnostic>,
+}
+
+/// Any block with a body, returning its type and labels, e.g. `dynamic "setting"`
+fn parse_unknown_block(i: &str) -> IResult<&str, String> {
+    map(
+        terminated(
+            pair(parse_identifier, many0(parse_resource_identifier)),
+            parse_block_body,
+        ),
+        |(kind, labels)| {
+            let labels: String = labels.iter().map(|l| format!(" \"{}\"", l)).collect();
+            format!("{}{}", kind, labels)
+        },
+    )(i)
+}
+
+/// Parses the whole content of `file`, failing on anything but blocks, whitespace and comments
+pub fn parse_file(file: &str, source: &str, mode: Mode) -> Result<ParsedFile, Diagnostic> {
+    let mut parsed = ParsedFile {
+        blocks: vec![],
+        spans: vec![],
+        warnings: vec![],
+    };
+
+    let mut i = source;
+    loop {
+        // Like `parse_blocks`, one block at a time to know where each starts
+        loop {
+            let (start, _) = comments::<ParserError<&str>>(i)
+                .map_err(|err| Diagnostic::new(file, source, err))?;
+            match parse_block(i) {
+                Ok((rest, block)) => {
+                    parsed.blocks.push(block);
+                    parsed.spans.push(Span::at(file, source, start));
+                    i = rest;
+                }
+                Err(Err::Error(_)) => {
+                    i = start;
+                    break;
+                }
+                Err(err) => return Err(Diagnostic::new(file, source, err)),
+            }
+        }
+
+        if i.is_empty() {
+            return Ok(parsed);
+        }
+
+        match parse_unknown_block(i) {
+            Ok((rest, block)) => {
+                let diagnostic =
+                    Diagnostic::at(file, source, i, format!("unsupported block `{}`", block));
+                match mode {
+                    Mode::Strict => return Err(diagnostic),
+                    Mode::Lenient => parsed.warnings.push(diagnostic),
+                }
+                i = rest;
+            }
+            // `parse_blocks` stopped on something that is no block at all, the error of the
+            // block parser is only worth reporting if it got past the start of it
+            Err(_) => {
+                return Err(match parse_block(i) {
+                    Err(Err::Error(e) | Err::Failure(e)) if e.input.len() < i.len() => {
+                        Diagnostic::new(file, source, Err::Error(e))
+                    }
+                    _ => Diagnostic::at(file, source, i, "expected a block".to_string()),
+                })
+            }
+        }
+    }
+}
+
+/// Parses a `.tfvars` file, which only holds `name = value` attributes
+pub fn parse_tfvars(file: &str, source: &str) -> Result<ResourceDefinition, Diagnostic> {
+    let (rest, attributes) =
+        terminated(many0(parse_resource_definition_property), comments)(source)
+            .map_err(|err| Diagnostic::new(file, source, err))?;
+
+    if rest.is_empty() {
+        return Ok(attributes);
+    }
+    Err(match parse_resource_definition_property(rest) {
+        Err(Err::Error(e) | Err::Failure(e)) if e.input.len() < rest.len() => {
+            Diagnostic::new(file, source, Err::Error(e))
+        }
+        _ => Diagnostic::at(file, source, rest, "expected an attribute".to_string()),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -190,18 +820,20 @@ mod tests {
         let expected = Resource {
             res_type: "res_type".to_string(),
             res_name: "res_name".to_string(),
+            res_blocks: vec![],
+            comments: vec![],
             res_def: vec![
                 (
                     "unique_name".to_string(),
-                    ResourcePropertyValue::VString("some_unique_name".to_owned()),
+                    Expression::String("some_unique_name".to_owned()),
                 ),
                 (
                     "another_property".to_string(),
-                    ResourcePropertyValue::VString(
-                        "Another property that contains spaces and =".to_owned(),
-                    ),
+                    Expression::String("Another property that contains spaces and =".to_owned()),
                 ),
             ],
+            key: None,
+            module: None,
         };
 
         let result = parse_resources(ap_resource).expect("Failed parsing the data");
@@ -209,6 +841,21 @@ mod tests {
         assert_eq!(result, ("", vec![expected]));
     }
 
+    #[test]
+    fn parse_jsonencode_of_hcl_as_a_function_call() {
+        for argument in ["local.actions", "{ say = \"hi\" }", "{ \"say\" = \"hi\" }"] {
+            let source = format!("jsonencode({})", argument);
+            let (rest, value) = parse_expression(&source).unwrap();
+
+            assert_eq!(rest, "");
+            assert!(
+                matches!(&value, Expression::FunctionCall { name, .. } if name == "jsonencode"),
+                "{:?}",
+                value
+            );
+        }
+    }
+
     #[test]
     fn parse_resource_json() {
         let ap_resource = "resource \"res_type\" \"res_name\" {
@@ -224,14 +871,16 @@ mod tests {
         let expected = Resource {
             res_type: "res_type".to_string(),
             res_name: "res_name".to_string(),
+            res_blocks: vec![],
+            comments: vec![],
             res_def: vec![
                 (
                     "unique_name".to_string(),
-                    ResourcePropertyValue::VString("some_unique_name".to_owned()),
+                    Expression::String("some_unique_name".to_owned()),
                 ),
                 (
                     "json_property".to_string(),
-                    ResourcePropertyValue::VJson(JsonValue::Object(HashMap::from([
+                    Expression::Json(JsonValue::Object(HashMap::from([
                         (
                             "string".to_string(),
                             JsonValue::Str("Just a string with spaces".to_string()),
@@ -255,6 +904,8 @@ mod tests {
                     ]))),
                 ),
             ],
+            key: None,
+            module: None,
         };
 
         let result = parse_resources(ap_resource).expect("Failed parsing the data");
@@ -272,16 +923,27 @@ mod tests {
         let expected = Resource {
             res_type: "res_type".to_string(),
             res_name: "res_name".to_string(),
+            res_blocks: vec![],
+            comments: vec![],
             res_def: vec![
                 (
                     "unique_name".to_string(),
-                    ResourcePropertyValue::VString("some_unique_name".to_owned()),
+                    Expression::String("some_unique_name".to_owned()),
                 ),
                 (
                     "set_property".to_string(),
-                    ResourcePropertyValue::VSet(vec!["a".to_string(), "b".to_string()]),
+                    Expression::FunctionCall {
+                        name: "toset".to_string(),
+                        args: vec![Expression::Tuple(vec![
+                            Expression::String("a".to_string()),
+                            Expression::String("b".to_string()),
+                        ])],
+                        expand_final: false,
+                    },
                 ),
             ],
+            key: None,
+            module: None,
         };
 
         let result = parse_resources(ap_resource).expect("Failed parsing the data");
@@ -299,19 +961,20 @@ mod tests {
         let expected = Resource {
             res_type: "res_type".to_string(),
             res_name: "res_name".to_string(),
+            res_blocks: vec![],
+            comments: vec![],
             res_def: vec![
                 (
                     "unique_name".to_string(),
-                    ResourcePropertyValue::VString("some_unique_name".to_owned()),
+                    Expression::String("some_unique_name".to_owned()),
                 ),
                 (
                     "reference_property".to_string(),
-                    ResourcePropertyValue::VReference {
-                        res_type: "parent_ref_type".to_string(),
-                        res_name: "parent_ref_name".to_string(),
-                    },
+                    Expression::traversal("parent_ref_type", &["parent_ref_name", "other"]),
                 ),
             ],
+            key: None,
+            module: None,
         };
 
         let result = parse_resources(ap_resource).expect("Failed parsing the data");
@@ -329,13 +992,17 @@ mod tests {
         let expected = Resource {
             res_type: "res_type".to_string(),
             res_name: "res_name".to_string(),
+            res_blocks: vec![],
+            comments: vec![],
             res_def: vec![
                 (
                     "unique_name".to_string(),
-                    ResourcePropertyValue::VString("some_unique_name".to_owned()),
+                    Expression::String("some_unique_name".to_owned()),
                 ),
-                ("null_property".to_string(), ResourcePropertyValue::VNull),
+                ("null_property".to_string(), Expression::Null),
             ],
+            key: None,
+            module: None,
         };
 
         let result = parse_resources(ap_resource).expect("Failed parsing the data");
@@ -354,20 +1021,244 @@ mod tests {
         let expected = Resource {
             res_type: "res_type".to_string(),
             res_name: "res_name".to_string(),
+            res_blocks: vec![],
+            comments: vec![],
             res_def: vec![
                 (
                     "unique_name".to_string(),
-                    ResourcePropertyValue::VString("some_unique_name".to_owned()),
+                    Expression::String("some_unique_name".to_owned()),
+                ),
+                ("true_property".to_string(), Expression::Boolean(true)),
+                ("false_property".to_string(), Expression::Boolean(false)),
+            ],
+            key: None,
+            module: None,
+        };
+
+        let result = parse_resources(ap_resource).expect("Failed parsing the data");
+
+        assert_eq!(result, ("", vec![expected]));
+    }
+
+    #[test]
+    fn parse_resource_comments() {
+        let ap_resource = "terraform {
+            required_providers {}
+        }
+
+        //Pre Survey
+        # resource \"not\" \"this_one\" {}
+        resource \"res_type\" \"res_name\" {
+            /* the name */
+            unique_name       = \"some_unique_name\" // trailing comment
+            # before the json
+            json_property     = jsonencode({
+                // inside the json
+                \"say\": \"hi\" /* after a value */
+            })
+        }
+        // at the end of the file";
+
+        let expected = Resource {
+            res_type: "res_type".to_string(),
+            res_name: "res_name".to_string(),
+            res_blocks: vec![],
+            comments: vec![
+                "Pre Survey".to_string(),
+                "resource \"not\" \"this_one\" {}".to_string(),
+            ],
+            res_def: vec![
+                (
+                    "unique_name".to_string(),
+                    Expression::String("some_unique_name".to_owned()),
+                ),
+                (
+                    "json_property".to_string(),
+                    Expression::Json(JsonValue::Object(HashMap::from([(
+                        "say".to_string(),
+                        JsonValue::Str("hi".to_string()),
+                    )]))),
+                ),
+            ],
+            key: None,
+            module: None,
+        };
+
+        let result = parse_resources(ap_resource).expect("Failed parsing the data");
+
+        assert_eq!(result, ("", vec![expected]));
+    }
+
+    #[test]
+    fn parse_other_blocks() {
+        let source = "terraform {
+          required_providers {
+            twilio = {
+              source  = \"twilio/twilio\"
+              version = \"0.17.0\"
+            }
+          }
+        }
+
+        module \"default_age_field\" {
+          count  = var.gender_field_type == \"default\" ? 1 : 0
+          source = \"../terraform-modules/age-field/default\"
+        }
+
+        moved {
+          from = twilio_autopilot_assistants_field_types_v1.age
+          to = module.default_age_field[0].twilio_autopilot_assistants_field_types_v1.age
+        }";
+
+        let (rest, blocks) = parse_blocks(source).expect("Failed parsing the data");
+
+        assert_eq!(rest, "");
+        assert_eq!(
+            blocks,
+            vec![
+                Block::Terraform(Body {
+                    attributes: vec![],
+                    blocks: vec![NestedBlock {
+                        kind: "required_providers".to_string(),
+                        labels: vec![],
+                        body: Body {
+                            attributes: vec![(
+                                "twilio".to_string(),
+                                Expression::Object(vec![
+                                    (
+                                        Expression::String("source".to_string()),
+                                        Expression::String("twilio/twilio".to_string())
+                                    ),
+                                    (
+                                        Expression::String("version".to_string()),
+                                        Expression::String("0.17.0".to_string())
+                                    ),
+                                ])
+                            )],
+                            blocks: vec![],
+                            comments: vec![],
+                        },
+                    }],
+                    comments: vec![],
+                }),
+                Block::Module(Module {
+                    name: "default_age_field".to_string(),
+                    body: Body {
+                        attributes: vec![
+                            (
+                                "count".to_string(),
+                                Expression::Conditional {
+                                    condition: Box::new(Expression::Binary(
+                                        BinaryOperator::Equal,
+                                        Box::new(Expression::traversal(
+                                            "var",
+                                            &["gender_field_type"]
+                                        )),
+                                        Box::new(Expression::String("default".to_string())),
+                                    )),
+                                    then: Box::new(Expression::Number(1.0)),
+                                    otherwise: Box::new(Expression::Number(0.0)),
+                                }
+                            ),
+                            (
+                                "source".to_string(),
+                                Expression::String(
+                                    "../terraform-modules/age-field/default".to_string()
+                                )
+                            ),
+                        ],
+                        blocks: vec![],
+                        comments: vec![],
+                    },
+                    key: None,
+                }),
+                Block::Moved(Body {
+                    attributes: vec![
+                        (
+                            "from".to_string(),
+                            Expression::traversal(
+                                "twilio_autopilot_assistants_field_types_v1",
+                                &["age"]
+                            )
+                        ),
+                        (
+                            "to".to_string(),
+                            Expression::Traversal(
+                                Box::new(Expression::Identifier("module".to_string())),
+                                vec![
+                                    TraversalStep::GetAttr("default_age_field".to_string()),
+                                    TraversalStep::Index(Expression::Number(0.0)),
+                                    TraversalStep::GetAttr(
+                                        "twilio_autopilot_assistants_field_types_v1".to_string()
+                                    ),
+                                    TraversalStep::GetAttr("age".to_string()),
+                                ]
+                            )
+                        ),
+                    ],
+                    blocks: vec![],
+                    comments: vec![],
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_resource_templates() {
+        let ap_resource = "resource \"res_type\" \"res_name\" {
+            empty_property    = \"\"
+            escaped_property  = \"say \\\"hi\\\"\\n$${not_interpolated}\"
+            template_property = \"${var.serverless_url}/redirect?name=${lower(each.key)}\"
+            json_property     = jsonencode({
+                \"uri\" : \"${var.serverless_url}/autopilotRedirect\"
+            })
+        }";
+
+        let expected = Resource {
+            res_type: "res_type".to_string(),
+            res_name: "res_name".to_string(),
+            res_blocks: vec![],
+            comments: vec![],
+            res_def: vec![
+                (
+                    "empty_property".to_string(),
+                    Expression::String("".to_string()),
+                ),
+                (
+                    "escaped_property".to_string(),
+                    Expression::String("say \"hi\"\n${not_interpolated}".to_string()),
                 ),
                 (
-                    "true_property".to_string(),
-                    ResourcePropertyValue::VBoolean(true),
+                    "template_property".to_string(),
+                    Expression::Template(vec![
+                        TemplatePart::Interpolation(Expression::traversal(
+                            "var",
+                            &["serverless_url"],
+                        )),
+                        TemplatePart::Literal("/redirect?name=".to_string()),
+                        TemplatePart::Interpolation(Expression::FunctionCall {
+                            name: "lower".to_string(),
+                            args: vec![Expression::traversal("each", &["key"])],
+                            expand_final: false,
+                        }),
+                    ]),
                 ),
                 (
-                    "false_property".to_string(),
-                    ResourcePropertyValue::VBoolean(false),
+                    "json_property".to_string(),
+                    Expression::Json(JsonValue::Object(HashMap::from([(
+                        "uri".to_string(),
+                        JsonValue::Template(vec![
+                            TemplatePart::Interpolation(Expression::traversal(
+                                "var",
+                                &["serverless_url"],
+                            )),
+                            TemplatePart::Literal("/autopilotRedirect".to_string()),
+                        ]),
+                    )]))),
                 ),
             ],
+            key: None,
+            module: None,
         };
 
         let result = parse_resources(ap_resource).expect("Failed parsing the data");
@@ -375,6 +1266,215 @@ mod tests {
         assert_eq!(result, ("", vec![expected]));
     }
 
+    #[test]
+    fn parse_expression_precedence() {
+        let var = |name: &str| Box::new(Expression::traversal("var", &[name]));
+
+        let (rest, expression) =
+            parse_expression("!var.a || var.b && var.c + 2 * var.d > 3 ? -1 : 0").unwrap();
+
+        assert_eq!(rest, "");
+        assert_eq!(
+            expression,
+            Expression::Conditional {
+                condition: Box::new(Expression::Binary(
+                    BinaryOperator::Or,
+                    Box::new(Expression::Unary(UnaryOperator::Not, var("a"))),
+                    Box::new(Expression::Binary(
+                        BinaryOperator::And,
+                        var("b"),
+                        Box::new(Expression::Binary(
+                            BinaryOperator::Greater,
+                            Box::new(Expression::Binary(
+                                BinaryOperator::Add,
+                                var("c"),
+                                Box::new(Expression::Binary(
+                                    BinaryOperator::Multiply,
+                                    Box::new(Expression::Number(2.0)),
+                                    var("d"),
+                                )),
+                            )),
+                            Box::new(Expression::Number(3.0)),
+                        )),
+                    )),
+                )),
+                then: Box::new(Expression::Unary(
+                    UnaryOperator::Negate,
+                    Box::new(Expression::Number(1.0)),
+                )),
+                otherwise: Box::new(Expression::Number(0.0)),
+            }
+        );
+
+        let (_, expression) = parse_expression("10 - 4 - 3").unwrap();
+        assert_eq!(
+            expression,
+            Expression::Binary(
+                BinaryOperator::Subtract,
+                Box::new(Expression::Binary(
+                    BinaryOperator::Subtract,
+                    Box::new(Expression::Number(10.0)),
+                    Box::new(Expression::Number(4.0)),
+                )),
+                Box::new(Expression::Number(3.0)),
+            )
+        );
+    }
+
+    #[test]
+    fn parse_expression_collections() {
+        let (rest, expression) = parse_expression(
+            "{for name, task in var.tasks : upper(name) => task.samples... if task.enabled}",
+        )
+        .unwrap();
+
+        assert_eq!(rest, "");
+        assert_eq!(
+            expression,
+            Expression::For(Box::new(ForExpression {
+                key_var: Some("name".to_string()),
+                value_var: "task".to_string(),
+                collection: Expression::traversal("var", &["tasks"]),
+                key: Some(Expression::FunctionCall {
+                    name: "upper".to_string(),
+                    args: vec![Expression::Identifier("name".to_string())],
+                    expand_final: false,
+                }),
+                value: Expression::traversal("task", &["samples"]),
+                condition: Some(Expression::traversal("task", &["enabled"])),
+                grouping: true,
+            }))
+        );
+
+        let (_, expression) = parse_expression("concat([1, 2,], local.extra...)[0]").unwrap();
+        assert_eq!(
+            expression,
+            Expression::Traversal(
+                Box::new(Expression::FunctionCall {
+                    name: "concat".to_string(),
+                    args: vec![
+                        Expression::Tuple(vec![Expression::Number(1.0), Expression::Number(2.0)]),
+                        Expression::traversal("local", &["extra"]),
+                    ],
+                    expand_final: true,
+                }),
+                vec![TraversalStep::Index(Expression::Number(0.0))],
+            )
+        );
+
+        let (_, expression) =
+            parse_expression("aws_lexv2models_slot.questions[*].slot_id").unwrap();
+        assert_eq!(
+            expression,
+            Expression::Traversal(
+                Box::new(Expression::Identifier("aws_lexv2models_slot".to_string())),
+                vec![
+                    TraversalStep::GetAttr("questions".to_string()),
+                    TraversalStep::FullSplat,
+                    TraversalStep::GetAttr("slot_id".to_string()),
+                ],
+            )
+        );
+
+        let (_, expression) = parse_expression("{ \"a b\" = null, c: true }").unwrap();
+        assert_eq!(
+            expression,
+            Expression::Object(vec![
+                (Expression::String("a b".to_string()), Expression::Null),
+                (
+                    Expression::String("c".to_string()),
+                    Expression::Boolean(true)
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_resource_heredoc() {
+        let ap_resource = "resource \"res_type\" \"res_name\" {
+            description = <<-EOT
+              Hello ${var.name},
+                welcome!
+            EOT
+            unique_name = \"some_unique_name\"
+        }";
+
+        let (rest, resources) = parse_resources(ap_resource).expect("Failed parsing the data");
+
+        assert_eq!(rest, "");
+        assert_eq!(
+            resources[0].res_def,
+            vec![
+                (
+                    "description".to_string(),
+                    Expression::Template(vec![
+                        TemplatePart::Literal("Hello ".to_string()),
+                        TemplatePart::Interpolation(Expression::traversal("var", &["name"])),
+                        TemplatePart::Literal(",\n  welcome!\n".to_string()),
+                    ]),
+                ),
+                (
+                    "unique_name".to_string(),
+                    Expression::String("some_unique_name".to_string()),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_file_strict() {
+        let source = "resource \"res_type\" \"res_name\" {}
+
+# unsupported
+dynamic \"setting\" {
+  name = \"x\"
+}
+";
+
+        let error = parse_file("main.tf", source, Mode::Strict).unwrap_err();
+        assert_eq!((error.line, error.column), (4, 1));
+        assert_eq!(error.message, "unsupported block `dynamic \"setting\"`");
+
+        let error = parse_file("main.tf", "locals {}\n= \"x\"", Mode::Strict).unwrap_err();
+        assert_eq!((error.line, error.column), (2, 1));
+        assert_eq!(error.message, "expected a block");
+    }
+
+    #[test]
+    fn parse_file_lenient() {
+        let source = "dynamic \"setting\" {
+  name = \"x\"
+}
+
+resource \"res_type\" \"res_name\" {}
+// the end
+";
+
+        let parsed = parse_file("main.tf", source, Mode::Lenient).unwrap();
+
+        assert_eq!(parsed.warnings.len(), 1);
+        assert_eq!(parsed.warnings[0].line, 1);
+        assert_eq!(only_resources(parsed.blocks).len(), 1);
+
+        let error = parse_file("main.tf", "locals {}\n= \"x\"", Mode::Lenient).unwrap_err();
+        assert_eq!((error.line, error.column), (2, 1));
+    }
+
+    #[test]
+    fn parse_main_tf() {
+        let source = std::fs::read_to_string("./main.tf").unwrap();
+
+        let (rest, blocks) = parse_blocks(&source).expect("Failed parsing the data");
+
+        let count = |f: fn(&Block) -> bool| blocks.iter().filter(|b| f(b)).count();
+
+        assert_eq!(rest, "");
+        assert_eq!(count(|b| matches!(b, Block::Terraform(_))), 1);
+        assert_eq!(count(|b| matches!(b, Block::Resource(_))), 12);
+        assert_eq!(count(|b| matches!(b, Block::Module(..))), 6);
+        assert_eq!(count(|b| matches!(b, Block::Moved(_))), 10);
+    }
+
     #[test]
     fn parse_test() {
         let ap_resource = "resource \"twilio_autopilot_assistants_tasks_v1\" \"development_pre_survey_redirect_function\" {
@@ -396,4 +1496,28 @@ mod tests {
 
         println!("{:#?}", result);
     }
+
+    #[test]
+    fn parse_tfvars_files() {
+        let attributes = parse_tfvars(
+            "terraform.tfvars",
+            "# the deployment\ngender_field_type = \"safespot\"\nunknown_synonyms = [\"idk\", \"?\"]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            attributes
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["gender_field_type", "unknown_synonyms"]
+        );
+
+        let error =
+            parse_tfvars("terraform.tfvars", "a = 1\nresource \"b\" \"c\" {}\n").unwrap_err();
+        assert_eq!(error.line, 2);
+        assert_eq!(error.message, "expected '='");
+
+        let error = parse_tfvars("terraform.tfvars", "a = 1\n{}\n").unwrap_err();
+        assert_eq!(error.message, "expected an attribute");
+    }
 }
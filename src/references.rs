@@ -0,0 +1,378 @@
+use crate::{
+    ast::{Expression, InstanceKey, NestedBlock, Resource, TraversalStep},
+    moved::without_keys,
+};
+use std::{collections::HashSet, fmt};
+
+/// Roots of traversals that never name a resource
+const RESERVED_ROOTS: [&str; 9] = [
+    "var",
+    "local",
+    "each",
+    "count",
+    "module",
+    "data",
+    "path",
+    "terraform",
+    "self",
+];
+
+/// A reference from a property of a resource to another resource, e.g.
+/// `assistant_sid = twilio_autopilot_assistants_v1.pre_survey.sid`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference<'a> {
+    pub from: &'a Resource,
+    /// The property holding the reference, with the nested blocks leading to it, e.g. `lifecycle.ignore_changes`
+    pub property: String,
+    pub target: &'a Resource,
+    /// The steps after the resource name, e.g. `.sid`
+    pub attribute: Vec<TraversalStep>,
+}
+
+/// A reference to a resource that isn't defined, shown for all the instances of the resource
+/// making it
+#[derive(Debug, Clone, PartialEq)]
+pub struct DanglingReference<'a> {
+    pub from: &'a Resource,
+    pub property: String,
+    pub res_type: String,
+    pub res_name: String,
+    /// The instance the reference indexes, e.g. `["hi"]`
+    pub key: Option<InstanceKey>,
+}
+
+impl fmt::Display for DanglingReference<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}.{} references {}.{}{}, which is not defined",
+            without_keys(&self.from.address()),
+            self.property,
+            self.res_type,
+            self.res_name,
+            self.key
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default()
+        )
+    }
+}
+
+/// The references between the resources of a file
+#[derive(Debug, Default)]
+pub struct ResourceGraph<'a> {
+    pub references: Vec<Reference<'a>>,
+    pub dangling: Vec<DanglingReference<'a>>,
+}
+
+impl<'a> ResourceGraph<'a> {
+    pub fn resolve(resources: &'a [Resource]) -> Self {
+        let mut graph = ResourceGraph::default();
+        for resource in resources {
+            let mut resolver = Resolver {
+                resources,
+                from: resource,
+                graph: &mut graph,
+            };
+            resolver.attributes("", &resource.res_def);
+            resolver.blocks("", &resource.res_blocks);
+        }
+
+        // A property may reference the same resource more than once, and every instance of a
+        // resource makes the references of the others
+        let mut reported = HashSet::new();
+        graph.dangling.retain(|d| reported.insert(d.to_string()));
+        graph
+    }
+}
+
+struct Resolver<'a, 'g> {
+    resources: &'a [Resource],
+    from: &'a Resource,
+    graph: &'g mut ResourceGraph<'a>,
+}
+
+impl<'a> Resolver<'a, '_> {
+    fn attributes(&mut self, prefix: &str, attributes: &[(String, Expression)]) {
+        for (name, value) in attributes {
+            let property = format!("{}{}", prefix, name);
+            self.expression(&property, value, &[]);
+        }
+    }
+
+    fn blocks(&mut self, prefix: &str, blocks: &[NestedBlock]) {
+        for block in blocks {
+            let prefix = format!("{}{}.", prefix, block.kind);
+            self.attributes(&prefix, &block.body.attributes);
+            self.blocks(&prefix, &block.body.blocks);
+        }
+    }
+
+    /// `scope` holds the names bound by the enclosing `for` expressions
+    fn expression(&mut self, property: &str, value: &Expression, scope: &[&str]) {
+        match value {
+            Expression::For(f) => {
+                self.expression(property, &f.collection, scope);
+                let mut inner = scope.to_vec();
+                inner.extend(f.key_var.as_deref());
+                inner.push(&f.value_var);
+                let parts = f.key.iter().chain([&f.value]).chain(f.condition.iter());
+                for part in parts {
+                    self.expression(property, part, &inner);
+                }
+                return;
+            }
+            Expression::Traversal(root, steps) => {
+                if let (Expression::Identifier(res_type), Some(TraversalStep::GetAttr(res_name))) =
+                    (root.as_ref(), steps.first())
+                {
+                    if !RESERVED_ROOTS.contains(&res_type.as_str())
+                        && !scope.contains(&res_type.as_str())
+                    {
+                        self.reference(property, res_type, res_name, &steps[1..]);
+                    }
+                }
+            }
+            _ => {}
+        }
+        for child in value.children() {
+            self.expression(property, child, scope);
+        }
+    }
+
+    fn reference(
+        &mut self,
+        property: &str,
+        res_type: &str,
+        res_name: &str,
+        attr: &[TraversalStep],
+    ) {
+        // The inputs of a module are inlined in its resources, so they may reference the
+        // resources of the module or of any module calling it, the closest one winning
+        let instances: Vec<&Resource> = module_ancestors(self.from.module.as_deref())
+            .into_iter()
+            .map(|module| {
+                self.resources
+                    .iter()
+                    .filter(|r| {
+                        r.res_type == res_type
+                            && r.res_name == res_name
+                            && r.module.as_deref() == module
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .find(|instances| !instances.is_empty())
+            .unwrap_or_default();
+        // The instance indexed by the reference, which must be one of them
+        let key = instance_key(attr);
+        let target = match &key {
+            Some(key) => instances.into_iter().find(|r| r.key.as_ref() == Some(key)),
+            None => instances.into_iter().next(),
+        };
+        match target {
+            Some(target) => self.graph.references.push(Reference {
+                from: self.from,
+                property: property.to_string(),
+                target,
+                attribute: attr.to_vec(),
+            }),
+            None => self.graph.dangling.push(DanglingReference {
+                from: self.from,
+                property: property.to_string(),
+                res_type: res_type.to_string(),
+                res_name: res_name.to_string(),
+                key,
+            }),
+        }
+    }
+}
+
+/// The key of the instance a traversal indexes with its first step, e.g. `["hi"]` for
+/// `type.name["hi"].sid`
+pub fn instance_key(attr: &[TraversalStep]) -> Option<InstanceKey> {
+    match attr.first() {
+        Some(TraversalStep::Index(Expression::Number(n))) if n.fract() == 0.0 && *n >= 0.0 => {
+            Some(InstanceKey::Index(*n as usize))
+        }
+        Some(TraversalStep::Index(Expression::String(key))) => Some(InstanceKey::Key(key.clone())),
+        _ => None,
+    }
+}
+
+/// `module` followed by the modules calling it, up to the root one, e.g.
+/// `module.a[0].module.b`, `module.a[0]` and the root
+fn module_ancestors(module: Option<&str>) -> Vec<Option<&str>> {
+    let mut ancestors = vec![module];
+    let mut module = module;
+    while let Some(current) = module {
+        module = current.rfind(".module.").map(|end| &current[..end]);
+        ancestors.push(module);
+    }
+    ancestors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_resources;
+
+    fn resources(source: &str) -> Vec<Resource> {
+        let (rest, resources) = parse_resources(source).unwrap();
+        assert_eq!(rest, "");
+        resources
+    }
+
+    #[test]
+    fn resolve_references() {
+        let resources = resources(
+            r#"
+resource "twilio_autopilot_assistants_v1" "pre_survey" {
+  unique_name = "pre-survey"
+}
+
+resource "twilio_autopilot_assistants_v1" "post_survey" {
+  unique_name = "post-survey"
+}
+
+resource "twilio_autopilot_tasks_v1" "greeting" {
+  assistant_sid = twilio_autopilot_assistants_v1.post_survey.sid
+  unique_name   = "greeting"
+  actions = jsonencode({
+    "say" : "Hi from ${twilio_autopilot_assistants_v1.post_survey.unique_name}"
+  })
+}
+"#,
+        );
+
+        let graph = ResourceGraph::resolve(&resources);
+
+        assert!(graph.dangling.is_empty());
+        assert_eq!(graph.references.len(), 2);
+        let reference = &graph.references[0];
+        assert_eq!(reference.from.res_name, "greeting");
+        assert_eq!(reference.property, "assistant_sid");
+        assert_eq!(reference.target.res_name, "post_survey");
+        assert_eq!(
+            reference.attribute,
+            vec![TraversalStep::GetAttr("sid".to_string())]
+        );
+        assert_eq!(graph.references[1].property, "actions");
+    }
+
+    #[test]
+    fn report_dangling_references() {
+        let resources = resources(
+            r#"
+resource "twilio_autopilot_task_samples_v1" "hello" {
+  assistant_sid = twilio_autopilot_assistants_v1.survey.sid
+  task_sid      = twilio_autopilot_tasks_v1.greeting.sid
+  language      = "en-US"
+  tagged_text   = "hello"
+}
+"#,
+        );
+
+        let graph = ResourceGraph::resolve(&resources);
+
+        assert!(graph.references.is_empty());
+        assert_eq!(
+            graph
+                .dangling
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "twilio_autopilot_task_samples_v1.hello.assistant_sid references twilio_autopilot_assistants_v1.survey, which is not defined",
+                "twilio_autopilot_task_samples_v1.hello.task_sid references twilio_autopilot_tasks_v1.greeting, which is not defined",
+            ]
+        );
+    }
+
+    #[test]
+    fn report_dangling_references_once_for_all_instances() {
+        let mut resources = resources(
+            r#"
+resource "twilio_autopilot_task_samples_v1" "hello" {
+  task_sid    = twilio_autopilot_tasks_v1.greeting.sid
+  tagged_text = "${twilio_autopilot_tasks_v1.greeting.sid} ${twilio_autopilot_tasks_v1.greeting.sid}"
+}
+"#,
+        );
+        let mut other = resources[0].clone();
+        resources[0].module = Some("module.survey[0]".to_string());
+        other.module = Some("module.survey[1]".to_string());
+        resources.push(other);
+
+        let graph = ResourceGraph::resolve(&resources);
+
+        assert_eq!(
+            graph
+                .dangling
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "module.survey.twilio_autopilot_task_samples_v1.hello.task_sid references twilio_autopilot_tasks_v1.greeting, which is not defined",
+                "module.survey.twilio_autopilot_task_samples_v1.hello.tagged_text references twilio_autopilot_tasks_v1.greeting, which is not defined",
+            ]
+        );
+    }
+
+    #[test]
+    fn resolve_references_to_instances() {
+        let mut resources = resources(
+            r#"
+resource "twilio_autopilot_tasks_v1" "greeting" {
+  unique_name = "greeting"
+}
+
+resource "twilio_autopilot_task_samples_v1" "hello" {
+  task_sid = twilio_autopilot_tasks_v1.greeting["hi"].sid
+  language = twilio_autopilot_tasks_v1.greeting["nope"].language
+}
+"#,
+        );
+        let mut hi = resources[0].clone();
+        resources[0].key = Some(InstanceKey::Key("hello".to_string()));
+        hi.key = Some(InstanceKey::Key("hi".to_string()));
+        resources.insert(1, hi);
+
+        let graph = ResourceGraph::resolve(&resources);
+
+        assert_eq!(graph.references.len(), 1);
+        assert_eq!(
+            graph.references[0].target.address(),
+            "twilio_autopilot_tasks_v1.greeting[\"hi\"]"
+        );
+        assert_eq!(
+            graph
+                .dangling
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["twilio_autopilot_task_samples_v1.hello.language references twilio_autopilot_tasks_v1.greeting[\"nope\"], which is not defined"]
+        );
+    }
+
+    #[test]
+    fn skip_variables_and_for_bindings() {
+        let resources = resources(
+            r#"
+resource "twilio_autopilot_assistants_v1" "survey" {
+  unique_name = var.name
+  tags        = { for tag, value in local.tags : tag => value.name if each.key != "" }
+  lifecycle {
+    ignore_changes = [twilio_autopilot_assistants_v1.survey]
+  }
+}
+"#,
+        );
+
+        let graph = ResourceGraph::resolve(&resources);
+
+        assert!(graph.dangling.is_empty());
+        assert_eq!(graph.references.len(), 1);
+        assert_eq!(graph.references[0].property, "lifecycle.ignore_changes");
+        assert!(graph.references[0].attribute.is_empty());
+    }
+}
@@ -0,0 +1,488 @@
+use crate::{
+    ast::{Block, Body, Expression, Module, NestedBlock, TemplatePart},
+    error::{Diagnostic, ParserError, Span},
+    json_utils::{self, sp, JsonValue},
+    parser::{self, parse_expression, parse_template_text, template_expression, Mode, ParsedFile},
+};
+use nom::{
+    branch::alt,
+    character::complete::char,
+    combinator::{cut, map},
+    error::context,
+    multi::separated_list0,
+    sequence::{delimited, preceded, separated_pair, terminated},
+};
+
+type IResult<I, O, E = ParserError<I>> = nom::IResult<I, O, E>;
+
+/// The key of the members that are comments, e.g. `"//": "Generated by a script"`
+const COMMENT: &str = "//";
+
+/// How many labels each type of block has, every label being an object around the body
+const BLOCK_LABELS: [(&str, usize); 12] = [
+    ("terraform", 0),
+    ("provider", 1),
+    ("variable", 1),
+    ("locals", 0),
+    ("output", 1),
+    ("module", 1),
+    ("moved", 0),
+    ("import", 0),
+    ("removed", 0),
+    ("check", 1),
+    ("resource", 2),
+    ("data", 2),
+];
+
+/// The nested blocks, as the JSON syntax can't tell them apart from object attributes without
+/// the schema of the resources
+const NESTED_BLOCK_LABELS: [(&str, usize); 9] = [
+    ("required_providers", 0),
+    ("backend", 1),
+    ("lifecycle", 0),
+    ("validation", 0),
+    ("precondition", 0),
+    ("postcondition", 0),
+    ("connection", 0),
+    ("provisioner", 1),
+    ("dynamic", 1),
+];
+
+/// The attributes holding an expression as a string instead of a template, like `type = string`
+const EXPRESSION_ATTRIBUTES: [(&str, &str); 5] = [
+    ("variable", "type"),
+    ("moved", "from"),
+    ("moved", "to"),
+    ("import", "to"),
+    ("removed", "from"),
+];
+
+/// A JSON value whose objects keep the order of their members, unlike `JsonValue`, so blocks
+/// come out in the same order as in the native syntax
+#[derive(Debug)]
+enum Node<'a> {
+    Object(Vec<Member<'a>>),
+    Array(Vec<Node<'a>>),
+    /// Unescaped, but its interpolations are still to be parsed
+    String(String),
+    /// A number, a boolean or null
+    Value(JsonValue),
+}
+
+#[derive(Debug)]
+struct Member<'a> {
+    key: String,
+    /// What is left of the source from the key, to locate the member
+    at: &'a str,
+    value: Node<'a>,
+}
+
+fn node(i: &str) -> IResult<&str, Node<'_>> {
+    preceded(
+        sp,
+        alt((
+            context(
+                "object",
+                map(
+                    preceded(
+                        char('{'),
+                        cut(terminated(
+                            separated_list0(preceded(sp, char(',')), member),
+                            preceded(sp, char('}')),
+                        )),
+                    ),
+                    Node::Object,
+                ),
+            ),
+            context(
+                "array",
+                map(
+                    preceded(
+                        char('['),
+                        cut(terminated(
+                            separated_list0(preceded(sp, char(',')), node),
+                            preceded(sp, char(']')),
+                        )),
+                    ),
+                    Node::Array,
+                ),
+            ),
+            // Unescaped, as the escapes of JSON aren't those of the templates in the string, e.g.
+            // `"${jsonencode({ \"say\" : \"hi\" })}"`
+            map(json_utils::plain_string, Node::String),
+            map(json_utils::json_value, Node::Value),
+        )),
+    )(i)
+}
+
+fn member(i: &str) -> IResult<&str, Member<'_>> {
+    let (at, _) = sp(i)?;
+    let (i, (key, value)) =
+        separated_pair(json_utils::plain_string, cut(preceded(sp, char(':'))), node)(at)?;
+    Ok((i, Member { key, at, value }))
+}
+
+/// Parses a `.tf.json` file into the same blocks as its native syntax counterpart
+pub fn parse_file(file: &str, source: &str, mode: Mode) -> Result<ParsedFile, Diagnostic> {
+    let (rest, root) =
+        delimited(sp, node, sp)(source).map_err(|err| Diagnostic::new(file, source, err))?;
+
+    let frontend = Frontend { file, source };
+    if !rest.is_empty() {
+        return Err(frontend.error(rest, "expected the end of the file".to_string()));
+    }
+    let members = match root {
+        Node::Object(members) => members,
+        _ => {
+            return Err(frontend.error(
+                source.trim_start(),
+                "expected an object of blocks".to_string(),
+            ))
+        }
+    };
+
+    let mut parsed = ParsedFile {
+        blocks: vec![],
+        spans: vec![],
+        warnings: vec![],
+    };
+    for member in members.iter().filter(|m| m.key != COMMENT) {
+        let labels = match BLOCK_LABELS.iter().find(|(kind, _)| *kind == member.key) {
+            Some((_, labels)) => *labels,
+            None => {
+                let diagnostic =
+                    frontend.error(member.at, format!("unsupported block `{}`", member.key));
+                match mode {
+                    Mode::Strict => return Err(diagnostic),
+                    Mode::Lenient => parsed.warnings.push(diagnostic),
+                }
+                continue;
+            }
+        };
+
+        for (at, labels, body) in frontend.labeled(member, labels)? {
+            let body = frontend.body(&member.key, body)?;
+            let block = match (member.key.as_str(), labels.as_slice()) {
+                ("terraform", []) => Block::Terraform(body),
+                ("provider", [name]) => Block::Provider(name.to_string(), body),
+                ("variable", [name]) => Block::Variable(name.to_string(), body),
+                ("locals", []) => Block::Locals(body),
+                ("output", [name]) => Block::Output(name.to_string(), body),
+                ("module", [name]) => Block::Module(Module {
+                    name: name.to_string(),
+                    body,
+                    key: None,
+                }),
+                ("moved", []) => Block::Moved(body),
+                ("import", []) => Block::Import(body),
+                ("removed", []) => Block::Removed(body),
+                ("check", [name]) => Block::Check(name.to_string(), body),
+                ("resource", [res_type, res_name]) => {
+                    Block::Resource(parser::resource(res_type, res_name, body))
+                }
+                ("data", [res_type, res_name]) => {
+                    Block::Data(parser::resource(res_type, res_name, body))
+                }
+                _ => unreachable!("the labels are read from BLOCK_LABELS"),
+            };
+            parsed.blocks.push(block);
+            parsed.spans.push(Span::at(file, source, at));
+        }
+    }
+
+    Ok(parsed)
+}
+
+struct Frontend<'a> {
+    file: &'a str,
+    source: &'a str,
+}
+
+impl<'a> Frontend<'a> {
+    fn error(&self, at: &str, message: String) -> Diagnostic {
+        Diagnostic::at(self.file, self.source, at, message)
+    }
+
+    /// The bodies of the blocks in `member`, with their labels and where the innermost starts,
+    /// e.g. `"resource": { "type": { "name": {...} } }` for two labels
+    #[allow(clippy::type_complexity)]
+    fn labeled<'n>(
+        &self,
+        member: &'n Member<'a>,
+        labels: usize,
+    ) -> Result<Vec<(&'a str, Vec<&'n str>, &'n Node<'a>)>, Diagnostic> {
+        if labels == 0 {
+            return Ok(self
+                .bodies(member)?
+                .into_iter()
+                .map(|body| (member.at, vec![], body))
+                .collect());
+        }
+
+        let members = match &member.value {
+            Node::Object(members) => members,
+            _ => {
+                return Err(self.error(
+                    member.at,
+                    format!("expected an object of labels in \"{}\"", member.key),
+                ))
+            }
+        };
+        let mut blocks = vec![];
+        for label in members.iter().filter(|m| m.key != COMMENT) {
+            for (at, mut labels, body) in self.labeled(label, labels - 1)? {
+                labels.insert(0, &label.key);
+                blocks.push((at, labels, body));
+            }
+        }
+        Ok(blocks)
+    }
+
+    /// A block may be given several times as an array of bodies
+    fn bodies<'n>(&self, member: &'n Member<'a>) -> Result<Vec<&'n Node<'a>>, Diagnostic> {
+        match &member.value {
+            body @ Node::Object(_) => Ok(vec![body]),
+            Node::Array(bodies) if bodies.iter().all(|b| matches!(b, Node::Object(_))) => {
+                Ok(bodies.iter().collect())
+            }
+            _ => Err(self.error(
+                member.at,
+                format!(
+                    "expected an object or an array of objects in \"{}\"",
+                    member.key
+                ),
+            )),
+        }
+    }
+
+    fn body(&self, kind: &str, node: &Node<'a>) -> Result<Body, Diagnostic> {
+        let mut body = Body {
+            attributes: vec![],
+            blocks: vec![],
+            comments: vec![],
+        };
+        let members = match node {
+            Node::Object(members) => members,
+            _ => return Ok(body),
+        };
+
+        for member in members {
+            if member.key == COMMENT {
+                match &member.value {
+                    Node::String(comment) => body.comments.push(comment.trim().to_string()),
+                    _ => return Err(self.error(member.at, "expected a comment".to_string())),
+                }
+                continue;
+            }
+
+            match NESTED_BLOCK_LABELS.iter().find(|(k, _)| *k == member.key) {
+                Some((_, labels)) => {
+                    for (_, labels, nested) in self.labeled(member, *labels)? {
+                        body.blocks.push(NestedBlock {
+                            kind: member.key.clone(),
+                            labels: labels.into_iter().map(str::to_string).collect(),
+                            body: self.body(&member.key, nested)?,
+                        });
+                    }
+                }
+                None => body
+                    .attributes
+                    .push((member.key.clone(), self.attribute(kind, member)?)),
+            }
+        }
+        Ok(body)
+    }
+
+    fn attribute(&self, kind: &str, member: &Member<'a>) -> Result<Expression, Diagnostic> {
+        if !EXPRESSION_ATTRIBUTES.contains(&(kind, member.key.as_str())) {
+            return self.expression(member, &member.value);
+        }
+        match &member.value {
+            Node::String(value) => match parse_expression(value) {
+                Ok((rest, expression)) if rest.trim().is_empty() => Ok(expression),
+                _ => Err(self.error(
+                    member.at,
+                    format!("\"{}\" is not a valid expression", value),
+                )),
+            },
+            _ => Err(self.error(
+                member.at,
+                format!("expected an expression as a string in \"{}\"", member.key),
+            )),
+        }
+    }
+
+    /// Strings are templates, so `"${var.name}"` is the same as `var.name` in the native syntax
+    fn expression(&self, member: &Member<'a>, node: &Node<'a>) -> Result<Expression, Diagnostic> {
+        Ok(match node {
+            Node::Object(members) => Expression::Object(
+                members
+                    .iter()
+                    .map(|m| {
+                        let value = self.expression(m, &m.value)?;
+                        Ok((Expression::String(m.key.clone()), value))
+                    })
+                    .collect::<Result<_, Diagnostic>>()?,
+            ),
+            Node::Array(values) => Expression::Tuple(
+                values
+                    .iter()
+                    .map(|v| self.expression(member, v))
+                    .collect::<Result<_, Diagnostic>>()?,
+            ),
+            Node::String(text) => match parse_template_text(text) {
+                Ok((_, parts)) => match parts.as_slice() {
+                    [TemplatePart::Interpolation(value)] => value.clone(),
+                    _ => template_expression(parts),
+                },
+                Err(_) => {
+                    return Err(
+                        self.error(member.at, format!("\"{}\" is not a valid template", text))
+                    )
+                }
+            },
+            Node::Value(JsonValue::Null) => Expression::Null,
+            Node::Value(JsonValue::Boolean(b)) => Expression::Boolean(*b),
+            Node::Value(JsonValue::Num(n)) => Expression::Number(*n),
+            // `node` parses the strings, objects and arrays itself
+            Node::Value(json) => Expression::Json(json.clone()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NATIVE: &str = r#"
+terraform {
+  required_providers {
+    twilio = {
+      source  = "twilio/twilio"
+      version = ">=0.4.0"
+    }
+  }
+}
+
+variable "tasks" {
+  type    = list(string)
+  default = ["greeting", "goodbye"]
+}
+
+# Generated by a script
+resource "twilio_autopilot_assistants_v1" "survey" {
+  unique_name = "demo_chatbot"
+}
+
+resource "twilio_autopilot_assistants_tasks_v1" "task" {
+  for_each      = toset(var.tasks)
+  unique_name   = each.key
+  assistant_sid = twilio_autopilot_assistants_v1.survey.sid
+  actions       = jsonencode({ "actions" : [{ "say" : "Hi from ${each.key}" }] })
+}
+
+moved {
+  from = twilio_autopilot_assistants_tasks_v1.greeting
+  to   = twilio_autopilot_assistants_tasks_v1.task["greeting"]
+}
+"#;
+
+    const JSON: &str = r#"{
+  "terraform": {
+    "required_providers": {
+      "twilio": { "source": "twilio/twilio", "version": ">=0.4.0" }
+    }
+  },
+  "variable": {
+    "tasks": { "type": "list(string)", "default": ["greeting", "goodbye"] }
+  },
+  "resource": {
+    "twilio_autopilot_assistants_v1": {
+      "survey": {
+        "//": "Generated by a script",
+        "unique_name": "demo_chatbot"
+      }
+    },
+    "twilio_autopilot_assistants_tasks_v1": {
+      "task": {
+        "for_each": "${toset(var.tasks)}",
+        "unique_name": "${each.key}",
+        "assistant_sid": "${twilio_autopilot_assistants_v1.survey.sid}",
+        "actions": "${jsonencode({ \"actions\" : [{ \"say\" : \"Hi from ${each.key}\" }] })}"
+      }
+    }
+  },
+  "moved": [
+    {
+      "from": "twilio_autopilot_assistants_tasks_v1.greeting",
+      "to": "twilio_autopilot_assistants_tasks_v1.task[\"greeting\"]"
+    }
+  ]
+}"#;
+
+    #[test]
+    fn parse_like_the_native_syntax() {
+        let native = parser::parse_file("main.tf", NATIVE, Mode::Strict).unwrap();
+        let json = parse_file("main.tf.json", JSON, Mode::Strict).unwrap();
+
+        assert_eq!(json.blocks, native.blocks);
+        assert!(json.warnings.is_empty());
+    }
+
+    #[test]
+    fn locate_blocks() {
+        let json = parse_file("main.tf.json", JSON, Mode::Strict).unwrap();
+
+        let spans: Vec<String> = json.spans.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            spans,
+            vec![
+                "main.tf.json:2:3",
+                "main.tf.json:8:5",
+                "main.tf.json:12:7",
+                "main.tf.json:18:7",
+                "main.tf.json:26:3",
+            ]
+        );
+    }
+
+    #[test]
+    fn report_unsupported_blocks() {
+        let source = "{\n  \"resources\": {}\n}";
+
+        let error = parse_file("main.tf.json", source, Mode::Strict).unwrap_err();
+        assert_eq!(
+            error.to_string().lines().next(),
+            Some("main.tf.json:2:3: unsupported block `resources`")
+        );
+
+        let parsed = parse_file("main.tf.json", source, Mode::Lenient).unwrap();
+        assert!(parsed.blocks.is_empty());
+        assert_eq!(parsed.warnings.len(), 1);
+    }
+
+    #[test]
+    fn report_invalid_files() {
+        let error = parse_file(
+            "main.tf.json",
+            "{\n  \"resource\": {\n    \"a\" \"b\"",
+            Mode::Strict,
+        )
+        .unwrap_err();
+        assert_eq!((error.line, error.column), (3, 9));
+
+        let error = parse_file("main.tf.json", "{ \"resource\": [] }", Mode::Strict).unwrap_err();
+        assert_eq!(
+            error.message,
+            "expected an object of labels in \"resource\""
+        );
+
+        let error = parse_file(
+            "main.tf.json",
+            "{ \"variable\": { \"a\": { \"type\": \"list(\" } } }",
+            Mode::Strict,
+        )
+        .unwrap_err();
+        assert_eq!(error.message, "\"list(\" is not a valid expression");
+    }
+}
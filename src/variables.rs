@@ -0,0 +1,369 @@
+use crate::{
+    ast::{Block, Body, Expression, NestedBlock},
+    error::Diagnostic,
+    eval::{self, Scope},
+    parser::{self, parse_expression},
+};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+/// A `-var-file` or `-var` option, applied in command line order
+#[derive(Debug, Clone, PartialEq)]
+pub enum VarOption {
+    File(PathBuf),
+    Value(String, String),
+}
+
+#[derive(Debug)]
+pub enum VariablesError {
+    Read {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+    Parse(Diagnostic),
+    /// A `-var` value that doesn't parse as the type of its variable
+    InvalidValue {
+        name: String,
+        value: String,
+    },
+    /// The variables declared or used without a value, sorted by name
+    Unset(Vec<String>),
+}
+
+impl fmt::Display for VariablesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VariablesError::Read { path, error } => {
+                write!(f, "could not read {}: {}", path.display(), error)
+            }
+            VariablesError::Parse(diagnostic) => write!(f, "{}", diagnostic),
+            VariablesError::InvalidValue { name, value } => write!(
+                f,
+                "the value \"{}\" of -var {} is not a valid expression",
+                value, name
+            ),
+            VariablesError::Unset(names) => write!(
+                f,
+                "no value for the variables {}, set them with -var, -var-file, a terraform.tfvars file or a default",
+                names.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for VariablesError {}
+
+/// The values of the variables of a configuration, from lowest to highest precedence:
+/// - `terraform.tfvars` in `dir`
+/// - `*.auto.tfvars` in `dir`, in alphabetical order
+/// - the `-var-file` and `-var` options
+///
+/// Variables without any of those fall back to the default of their `variable` block.
+/// Every variable declared or used by the blocks must end up with a value.
+pub fn resolve(
+    blocks: &[Block],
+    dir: &Path,
+    options: &[VarOption],
+) -> Result<HashMap<String, Expression>, VariablesError> {
+    let declarations = declarations(blocks);
+
+    let mut values = HashMap::new();
+    for path in tfvars_files(dir)? {
+        values.extend(read_tfvars(&path)?);
+    }
+
+    for option in options {
+        match option {
+            VarOption::File(path) => values.extend(read_tfvars(path)?),
+            VarOption::Value(name, value) => {
+                let value = match declarations
+                    .get(name.as_str())
+                    .and_then(|b| declared_type(b))
+                {
+                    None | Some("string") => Expression::String(value.clone()),
+                    Some(_) => match parse_expression(value) {
+                        Ok((rest, parsed)) if rest.trim().is_empty() && is_constant(&parsed) => {
+                            constant(&parsed)
+                        }
+                        _ => {
+                            return Err(VariablesError::InvalidValue {
+                                name: name.clone(),
+                                value: value.clone(),
+                            })
+                        }
+                    },
+                };
+                values.insert(name.clone(), value);
+            }
+        }
+    }
+
+    bind(blocks, values)
+}
+
+/// Completes `values` with the defaults of the `variable` blocks, and checks every variable
+/// declared or used by the blocks has a value. The values may be any expression, e.g. the
+/// arguments of a module call.
+pub fn bind(
+    blocks: &[Block],
+    mut values: HashMap<String, Expression>,
+) -> Result<HashMap<String, Expression>, VariablesError> {
+    let declarations = declarations(blocks);
+    for (name, body) in &declarations {
+        let default = body.attributes.iter().find(|(k, _)| k == "default");
+        if let (false, Some((_, default))) = (values.contains_key(*name), default) {
+            values.insert(name.to_string(), constant(default));
+        }
+    }
+
+    let mut required: BTreeSet<&str> = declarations.keys().copied().collect();
+    required.extend(used_variables(blocks));
+    let unset: Vec<String> = required
+        .into_iter()
+        .filter(|name| !values.contains_key(*name))
+        .map(str::to_string)
+        .collect();
+    if !unset.is_empty() {
+        return Err(VariablesError::Unset(unset));
+    }
+
+    Ok(values)
+}
+
+fn declarations(blocks: &[Block]) -> HashMap<&str, &Body> {
+    blocks
+        .iter()
+        .filter_map(|block| match block {
+            Block::Variable(name, body) => Some((name.as_str(), body)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Values can't reference anything, but may still be written as expressions, e.g. `60 * 60`
+fn constant(value: &Expression) -> Expression {
+    eval::evaluate(value, &Scope::default())
+}
+
+/// Whether the value only holds literals, e.g. `["a", "b"]` but not `a` or `var.a`
+fn is_constant(value: &Expression) -> bool {
+    let mut constant = true;
+    value.walk(&mut |e| {
+        if matches!(e, Expression::Identifier(_)) {
+            constant = false;
+        }
+    });
+    constant
+}
+
+/// The name of a primitive `type`, e.g. `string` or `number`, or the function of a complex one
+fn declared_type(body: &Body) -> Option<&str> {
+    match body.attributes.iter().find(|(k, _)| k == "type")? {
+        (_, Expression::Identifier(name)) => Some(name),
+        (_, Expression::FunctionCall { name, .. }) => Some(name),
+        _ => None,
+    }
+}
+
+fn tfvars_files(dir: &Path) -> Result<Vec<PathBuf>, VariablesError> {
+    let read_error = |error| VariablesError::Read {
+        path: dir.to_path_buf(),
+        error,
+    };
+
+    let mut auto_files = vec![];
+    for entry in fs::read_dir(dir).map_err(read_error)? {
+        let path = entry.map_err(read_error)?.path();
+        let is_auto = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map(|name| name.ends_with(".auto.tfvars"))
+            .unwrap_or(false);
+        if is_auto && path.is_file() {
+            auto_files.push(path);
+        }
+    }
+    auto_files.sort();
+
+    let terraform_tfvars = dir.join("terraform.tfvars");
+    let files = terraform_tfvars.is_file().then_some(terraform_tfvars);
+    Ok(files.into_iter().chain(auto_files).collect())
+}
+
+fn read_tfvars(path: &Path) -> Result<Vec<(String, Expression)>, VariablesError> {
+    let source = fs::read_to_string(path).map_err(|error| VariablesError::Read {
+        path: path.to_path_buf(),
+        error,
+    })?;
+    let attributes = parser::parse_tfvars(&path.display().to_string(), &source)
+        .map_err(VariablesError::Parse)?;
+
+    Ok(attributes
+        .into_iter()
+        .map(|(name, value)| (name, constant(&value)))
+        .collect())
+}
+
+/// The names of the variables referenced anywhere in the blocks
+fn used_variables(blocks: &[Block]) -> BTreeSet<&str> {
+    fn body<'a>(
+        attributes: &'a [(String, Expression)],
+        blocks: &'a [NestedBlock],
+        used: &mut BTreeSet<&'a str>,
+    ) {
+        for (_, value) in attributes {
+            used.extend(value.variables());
+        }
+        for block in blocks {
+            body(&block.body.attributes, &block.body.blocks, used);
+        }
+    }
+
+    let mut used = BTreeSet::new();
+    for block in blocks {
+        match block {
+            Block::Resource(resource) | Block::Data(resource) => {
+                body(&resource.res_def, &resource.res_blocks, &mut used)
+            }
+            Block::Module(module) => body(&module.body.attributes, &module.body.blocks, &mut used),
+            Block::Terraform(b)
+            | Block::Provider(_, b)
+            | Block::Locals(b)
+            | Block::Output(_, b)
+            | Block::Moved(b)
+            | Block::Import(b)
+            | Block::Removed(b)
+            | Block::Check(_, b) => body(&b.attributes, &b.blocks, &mut used),
+            // Defaults and validations can't reference other variables
+            Block::Variable(..) => {}
+        }
+    }
+    used
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parser::parse_blocks, test_utils::directory};
+
+    fn blocks(source: &str) -> Vec<Block> {
+        let (rest, blocks) = parse_blocks(source).unwrap();
+        assert_eq!(rest, "");
+        blocks
+    }
+
+    const SOURCE: &str = r#"
+variable "gender_field_type" {
+  type    = string
+  default = "default"
+}
+
+variable "max_attempts" {
+  type = number
+}
+
+module "safespot_gender_field" {
+  count  = var.gender_field_type == "safespot" ? 1 : 0
+  source = "../terraform-modules/gender-field/safespot"
+}
+
+resource "twilio_autopilot_assistants_tasks_v1" "redirect" {
+  unique_name = "redirect"
+  actions     = jsonencode({ "redirect" : "${var.serverless_url}/autopilotRedirect" })
+}
+"#;
+
+    #[test]
+    fn resolve_by_precedence() {
+        let dir = directory(
+            "precedence",
+            &[
+                (
+                    "terraform.tfvars",
+                    "max_attempts = 2\nserverless_url = \"https://a.example\"\n",
+                ),
+                ("b.auto.tfvars", "max_attempts = 4 # the last one wins\n"),
+                ("a.auto.tfvars", "max_attempts = 3\n"),
+                ("extra.tfvars", "serverless_url = \"https://b.example\"\n"),
+            ],
+        );
+
+        let values = resolve(
+            &blocks(SOURCE),
+            &dir,
+            &[
+                VarOption::File(dir.join("extra.tfvars")),
+                VarOption::Value("gender_field_type".to_string(), "safespot".to_string()),
+                VarOption::Value("max_attempts".to_string(), "5 + 1".to_string()),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(
+            values["gender_field_type"],
+            Expression::String("safespot".to_string())
+        );
+        assert_eq!(values["max_attempts"], Expression::Number(6.0));
+        assert_eq!(
+            values["serverless_url"],
+            Expression::String("https://b.example".to_string())
+        );
+
+        let values = resolve(&blocks(SOURCE), &dir, &[]).unwrap();
+        assert_eq!(
+            values["gender_field_type"],
+            Expression::String("default".to_string())
+        );
+        assert_eq!(values["max_attempts"], Expression::Number(4.0));
+    }
+
+    #[test]
+    fn report_unset_variables() {
+        let dir = directory("unset", &[]);
+
+        let error = resolve(&blocks(SOURCE), &dir, &[]).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "no value for the variables max_attempts, serverless_url, set them with -var, -var-file, a terraform.tfvars file or a default"
+        );
+    }
+
+    #[test]
+    fn report_invalid_files_and_values() {
+        let dir = directory("invalid", &[("broken.tfvars", "max_attempts = \n")]);
+
+        let error = resolve(
+            &blocks(SOURCE),
+            &dir,
+            &[VarOption::Value(
+                "max_attempts".to_string(),
+                "two".to_string(),
+            )],
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the value \"two\" of -var max_attempts is not a valid expression"
+        );
+
+        let error = resolve(
+            &blocks(SOURCE),
+            &dir,
+            &[VarOption::File(dir.join("broken.tfvars"))],
+        )
+        .unwrap_err();
+        assert!(matches!(error, VariablesError::Parse(_)));
+
+        let error = resolve(
+            &blocks(SOURCE),
+            &dir,
+            &[VarOption::File(dir.join("missing.tfvars"))],
+        )
+        .unwrap_err();
+        assert!(error.to_string().starts_with("could not read "));
+    }
+}
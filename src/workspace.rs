@@ -0,0 +1,242 @@
+use crate::{
+    ast::Block,
+    error::{Diagnostic, Span},
+    parser::{self, Mode},
+    tf_json,
+};
+use std::{
+    collections::HashMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug)]
+pub enum WorkspaceError {
+    Read {
+        path: PathBuf,
+        error: io::Error,
+    },
+    Parse(Diagnostic),
+    /// A directory without any configuration file
+    Empty {
+        dir: PathBuf,
+    },
+    /// Two blocks with the same address, in the same file or not
+    Duplicate {
+        address: String,
+        first: Span,
+        second: Span,
+    },
+}
+
+impl fmt::Display for WorkspaceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorkspaceError::Read { path, error } => {
+                write!(f, "could not read {}: {}", path.display(), error)
+            }
+            WorkspaceError::Parse(diagnostic) => write!(f, "{}", diagnostic),
+            WorkspaceError::Empty { dir } => {
+                write!(f, "there are no .tf files in {}", dir.display())
+            }
+            WorkspaceError::Duplicate {
+                address,
+                first,
+                second,
+            } => write!(
+                f,
+                "{}: {} is already declared at {}",
+                second, address, first
+            ),
+        }
+    }
+}
+
+impl std::error::Error for WorkspaceError {}
+
+/// The configuration of a directory, like Terraform reads it
+#[derive(Debug)]
+pub struct Workspace {
+    /// Where the `.tfvars` files and the local modules are looked up from
+    pub dir: PathBuf,
+    /// The blocks of every file, in file-name order
+    pub blocks: Vec<Block>,
+    /// Where each of the blocks starts
+    pub spans: Vec<Span>,
+    /// The blocks and files skipped
+    pub warnings: Vec<String>,
+}
+
+impl Workspace {
+    /// Reads the `.tf` and `.tf.json` files of the directory at `path`, or only the file at
+    /// `path`
+    pub fn load(path: &Path, mode: Mode) -> Result<Self, WorkspaceError> {
+        let (dir, files) = match path.is_dir() {
+            true => (path.to_path_buf(), configuration_files(path)?),
+            false => {
+                let dir = match path.parent() {
+                    Some(dir) if !dir.as_os_str().is_empty() => dir,
+                    _ => Path::new("."),
+                };
+                (dir.to_path_buf(), vec![path.to_path_buf()])
+            }
+        };
+        if files.is_empty() {
+            return Err(WorkspaceError::Empty { dir });
+        }
+
+        let mut workspace = Workspace {
+            dir,
+            blocks: vec![],
+            spans: vec![],
+            warnings: vec![],
+        };
+        let mut declared: HashMap<String, Span> = HashMap::new();
+        for path in files {
+            let file = path.display().to_string();
+            let source = fs::read_to_string(&path).map_err(|error| WorkspaceError::Read {
+                path: path.clone(),
+                error,
+            })?;
+            let parsed = match file.ends_with(".tf.json") {
+                true => tf_json::parse_file(&file, &source, mode),
+                false => parser::parse_file(&file, &source, mode),
+            }
+            .map_err(WorkspaceError::Parse)?;
+            workspace
+                .warnings
+                .extend(parsed.warnings.iter().map(ToString::to_string));
+
+            for (block, span) in parsed.blocks.iter().zip(&parsed.spans) {
+                let address = match declared_address(block) {
+                    Some(address) => address,
+                    None => continue,
+                };
+                if let Some(first) = declared.get(&address) {
+                    return Err(WorkspaceError::Duplicate {
+                        address,
+                        first: first.clone(),
+                        second: span.clone(),
+                    });
+                }
+                declared.insert(address, span.clone());
+            }
+            workspace.blocks.extend(parsed.blocks);
+            workspace.spans.extend(parsed.spans);
+        }
+        Ok(workspace)
+    }
+}
+
+/// The `.tf` and `.tf.json` files of `dir`, sorted by name
+fn configuration_files(dir: &Path) -> Result<Vec<PathBuf>, WorkspaceError> {
+    let read_error = |error| WorkspaceError::Read {
+        path: dir.to_path_buf(),
+        error,
+    };
+
+    let mut files = vec![];
+    for entry in fs::read_dir(dir).map_err(read_error)? {
+        let path = entry.map_err(read_error)?.path();
+        let is_configuration = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map(|name| name.ends_with(".tf") || name.ends_with(".tf.json"))
+            .unwrap_or(false);
+        if is_configuration && path.is_file() {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// The address of the blocks that can only be declared once in a configuration
+fn declared_address(block: &Block) -> Option<String> {
+    match block {
+        Block::Resource(resource) => Some(resource.address()),
+        Block::Data(resource) => Some(format!("data.{}", resource.address())),
+        Block::Module(module) => Some(module.address()),
+        Block::Variable(name, _) => Some(format!("var.{}", name)),
+        Block::Output(name, _) => Some(format!("output.{}", name)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::directory;
+
+    const MAIN: &str = r#"
+resource "twilio_autopilot_assistants_v1" "survey" {
+  unique_name = var.name
+}
+"#;
+
+    const VARIABLES: &str = r#"
+variable "name" {
+  type = string
+}
+"#;
+
+    #[test]
+    fn load_every_file() {
+        let dir = directory(
+            "every",
+            &[
+                ("variables.tf", VARIABLES),
+                ("main.tf", MAIN),
+                ("terraform.tfvars", "name = \"survey\"\n"),
+                (
+                    "generated.tf.json",
+                    r#"{ "output": { "survey": { "value": "${twilio_autopilot_assistants_v1.survey.sid}" } } }"#,
+                ),
+            ],
+        );
+
+        let workspace = Workspace::load(&dir, Mode::Strict).unwrap();
+
+        assert_eq!(workspace.dir, *dir);
+        assert!(matches!(
+            workspace.blocks.as_slice(),
+            [Block::Output(..), Block::Resource(_), Block::Variable(..)]
+        ));
+        assert!(workspace.warnings.is_empty());
+
+        let workspace = Workspace::load(&dir.join("main.tf"), Mode::Strict).unwrap();
+        assert_eq!(workspace.dir, *dir);
+        assert_eq!(workspace.blocks.len(), 1);
+    }
+
+    #[test]
+    fn report_duplicates_across_files() {
+        let dir = directory(
+            "duplicates",
+            &[
+                ("main.tf", MAIN),
+                ("other.tf", &format!("# The same again\n{}", MAIN)),
+            ],
+        );
+
+        let error = Workspace::load(&dir, Mode::Strict).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "{}:3:1: twilio_autopilot_assistants_v1.survey is already declared at {}:2:1",
+                dir.join("other.tf").display(),
+                dir.join("main.tf").display()
+            )
+        );
+    }
+
+    #[test]
+    fn report_empty_directories() {
+        let dir = directory("empty", &[("terraform.tfvars", "name = \"survey\"\n")]);
+
+        let error = Workspace::load(&dir, Mode::Strict).unwrap_err();
+
+        assert!(matches!(error, WorkspaceError::Empty { .. }));
+    }
+}
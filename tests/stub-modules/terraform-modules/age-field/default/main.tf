@@ -0,0 +1,30 @@
+# A synthetic stand-in for the module main.tf calls, written for the tests and not taken from
+# the terraform-modules repository
+variable "bot_sid" {}
+variable "unknown_value" {}
+variable "unknown_synonyms" {
+  type = list(string)
+}
+
+resource "twilio_autopilot_assistants_field_types_v1" "age" {
+  unique_name   = "Age"
+  assistant_sid = var.bot_sid
+}
+
+resource "twilio_autopilot_assistants_field_types_field_values_v1" "number_age_group" {
+  for_each       = toset(["1", "2", "3", "4", "5", "6", "7", "8", "9", "10", "11", "12", "13", "14", "15", "16", "17", "18", var.unknown_value])
+  assistant_sid  = var.bot_sid
+  field_type_sid = twilio_autopilot_assistants_field_types_v1.age.sid
+  value          = each.key
+  language       = "en-US"
+}
+
+resource "twilio_autopilot_assistants_field_types_field_values_v1" "unknown_age_synonym_group" {
+  depends_on     = [twilio_autopilot_assistants_field_types_field_values_v1.number_age_group]
+  for_each       = toset(var.unknown_synonyms)
+  assistant_sid  = var.bot_sid
+  field_type_sid = twilio_autopilot_assistants_field_types_v1.age.sid
+  synonym_of     = var.unknown_value
+  value          = each.key
+  language       = "en-US"
+}
@@ -0,0 +1,56 @@
+# A synthetic stand-in for the module main.tf calls, written for the tests and not taken from
+# the terraform-modules repository
+variable "bot_sid" {}
+
+resource "twilio_autopilot_assistants_field_types_v1" "gender" {
+  unique_name   = "Gender"
+  assistant_sid = var.bot_sid
+}
+
+resource "twilio_autopilot_assistants_field_types_field_values_v1" "gender_group" {
+  for_each       = toset(["Boy", "Girl", "Non-Binary", "Unknown"])
+  assistant_sid  = var.bot_sid
+  field_type_sid = twilio_autopilot_assistants_field_types_v1.gender.sid
+  value          = each.key
+  language       = "en-US"
+}
+
+resource "twilio_autopilot_assistants_field_types_field_values_v1" "gender_boy_synonym_group" {
+  depends_on     = [twilio_autopilot_assistants_field_types_field_values_v1.gender_group]
+  for_each       = toset(["male", "man", "guy"])
+  assistant_sid  = var.bot_sid
+  field_type_sid = twilio_autopilot_assistants_field_types_v1.gender.sid
+  synonym_of     = "Boy"
+  value          = each.key
+  language       = "en-US"
+}
+
+resource "twilio_autopilot_assistants_field_types_field_values_v1" "gender_girl_synonym_group" {
+  depends_on     = [twilio_autopilot_assistants_field_types_field_values_v1.gender_group]
+  for_each       = toset(["female", "woman", "lady"])
+  assistant_sid  = var.bot_sid
+  field_type_sid = twilio_autopilot_assistants_field_types_v1.gender.sid
+  synonym_of     = "Girl"
+  value          = each.key
+  language       = "en-US"
+}
+
+resource "twilio_autopilot_assistants_field_types_field_values_v1" "gender_nonbinary_synonym_group" {
+  depends_on     = [twilio_autopilot_assistants_field_types_field_values_v1.gender_group]
+  for_each       = toset(["non binary", "nonbinary", "agender"])
+  assistant_sid  = var.bot_sid
+  field_type_sid = twilio_autopilot_assistants_field_types_v1.gender.sid
+  synonym_of     = "Non-Binary"
+  value          = each.key
+  language       = "en-US"
+}
+
+resource "twilio_autopilot_assistants_field_types_field_values_v1" "gender_unknown_synonym_group" {
+  depends_on     = [twilio_autopilot_assistants_field_types_field_values_v1.gender_group]
+  for_each       = toset(["prefer not to answer", "X"])
+  assistant_sid  = var.bot_sid
+  field_type_sid = twilio_autopilot_assistants_field_types_v1.gender.sid
+  synonym_of     = "Unknown"
+  value          = each.key
+  language       = "en-US"
+}
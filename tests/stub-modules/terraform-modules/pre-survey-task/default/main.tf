@@ -0,0 +1,49 @@
+# A synthetic stand-in for the module main.tf calls, written for the tests and not taken from
+# the terraform-modules repository
+variable "bot_sid" {}
+
+resource "twilio_autopilot_assistants_tasks_v1" "survey" {
+  unique_name   = "survey"
+  assistant_sid = var.bot_sid
+  actions = jsonencode({
+    "actions" : [
+      {
+        "remember" : {
+          "at" : "survey"
+        }
+      },
+      {
+        "collect" : {
+          "on_complete" : {
+            "redirect" : "task://redirect_function"
+          },
+          "name" : "collect_survey",
+          "questions" : [
+            {
+              "type" : "Age",
+              "validate" : {
+                "max_attempts" : {
+                  "redirect" : "task://redirect_function",
+                  "num_attempts" : 2
+                }
+              },
+              "question" : "How old are you?",
+              "name" : "age"
+            },
+            {
+              "type" : "Gender",
+              "validate" : {
+                "max_attempts" : {
+                  "redirect" : "task://redirect_function",
+                  "num_attempts" : 2
+                }
+              },
+              "question" : "What is your gender?",
+              "name" : "gender"
+            }
+          ]
+        }
+      }
+    ]
+  })
+}